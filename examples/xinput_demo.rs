@@ -19,6 +19,7 @@ fn main() {
   // If we fail to load the rest of the demo clearly can't run, so we'll just do
   // an unwrap here.
   let handle = rusty_xinput::XInputHandle::load_default().unwrap();
+  info!("{:?}", handle.features());
 
   // Quick rumble test. Note that the controller might not _have_ rumble.
   trace!("rumble on:{:?}", handle.set_state(0, 1000, 1000));
@@ -0,0 +1,541 @@
+//! Lets the rest of your program talk to "some controller source" instead of
+//! directly to a loaded DLL.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[cfg(windows)]
+use super::XInputHandle;
+use super::{
+  BatteryLevel, BatteryType, XInputBatteryInformation, XInputCapabilities,
+  XInputOptionalFnUsageError, XInputState, XInputUsageError, XINPUT_CAPABILITIES,
+  XINPUT_DEVSUBTYPE_GAMEPAD, XINPUT_DEVTYPE_GAMEPAD, XINPUT_GAMEPAD, XINPUT_GAMEPAD_GUIDE,
+  XINPUT_KEYSTROKE, XINPUT_STATE, XINPUT_VIBRATION,
+};
+
+/// Something that can be polled for controller data like an `XInputHandle`.
+///
+/// `XInputHandle` is the "real" implementation, and `MockBackend` is an
+/// in-memory implementation that you can script from test code. If your
+/// gameplay code takes a `ControllerBackend` instead of an `XInputHandle` then
+/// you can run it against either one.
+///
+/// The methods all have the same meaning and error conditions as the
+/// `XInputHandle` methods of the same name.
+pub trait ControllerBackend {
+  /// See `XInputHandle::get_state`
+  fn get_state(&self, user_index: u32) -> Result<XInputState, XInputUsageError>;
+
+  /// See `XInputHandle::get_state_ex`
+  fn get_state_ex(&self, user_index: u32) -> Result<XInputState, XInputUsageError>;
+
+  /// See `XInputHandle::set_state`
+  fn set_state(
+    &self,
+    user_index: u32,
+    left_motor_speed: u16,
+    right_motor_speed: u16,
+  ) -> Result<(), XInputUsageError>;
+
+  /// See `XInputHandle::get_capabilities_info`
+  fn get_capabilities(&self, user_index: u32) -> Result<XInputCapabilities, XInputUsageError>;
+
+  /// See `XInputHandle::get_keystroke`
+  fn get_keystroke(
+    &self,
+    user_index: u32,
+  ) -> Result<Option<XINPUT_KEYSTROKE>, XInputOptionalFnUsageError>;
+
+  /// See `XInputHandle::get_gamepad_battery_information`
+  fn get_gamepad_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError>;
+
+  /// See `XInputHandle::get_headset_battery_information`
+  fn get_headset_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError>;
+}
+
+#[cfg(windows)]
+impl ControllerBackend for XInputHandle {
+  fn get_state(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    XInputHandle::get_state(self, user_index)
+  }
+
+  fn get_state_ex(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    XInputHandle::get_state_ex(self, user_index)
+  }
+
+  fn set_state(
+    &self,
+    user_index: u32,
+    left_motor_speed: u16,
+    right_motor_speed: u16,
+  ) -> Result<(), XInputUsageError> {
+    XInputHandle::set_state(self, user_index, left_motor_speed, right_motor_speed)
+  }
+
+  fn get_capabilities(&self, user_index: u32) -> Result<XInputCapabilities, XInputUsageError> {
+    XInputHandle::get_capabilities_info(self, user_index)
+  }
+
+  fn get_keystroke(
+    &self,
+    user_index: u32,
+  ) -> Result<Option<XINPUT_KEYSTROKE>, XInputOptionalFnUsageError> {
+    XInputHandle::get_keystroke(self, user_index)
+  }
+
+  fn get_gamepad_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    XInputHandle::get_gamepad_battery_information(self, user_index)
+  }
+
+  fn get_headset_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    XInputHandle::get_headset_battery_information(self, user_index)
+  }
+}
+
+impl<B: ControllerBackend + ?Sized> ControllerBackend for &B {
+  fn get_state(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    (**self).get_state(user_index)
+  }
+
+  fn get_state_ex(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    (**self).get_state_ex(user_index)
+  }
+
+  fn set_state(
+    &self,
+    user_index: u32,
+    left_motor_speed: u16,
+    right_motor_speed: u16,
+  ) -> Result<(), XInputUsageError> {
+    (**self).set_state(user_index, left_motor_speed, right_motor_speed)
+  }
+
+  fn get_capabilities(&self, user_index: u32) -> Result<XInputCapabilities, XInputUsageError> {
+    (**self).get_capabilities(user_index)
+  }
+
+  fn get_keystroke(
+    &self,
+    user_index: u32,
+  ) -> Result<Option<XINPUT_KEYSTROKE>, XInputOptionalFnUsageError> {
+    (**self).get_keystroke(user_index)
+  }
+
+  fn get_gamepad_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    (**self).get_gamepad_battery_information(user_index)
+  }
+
+  fn get_headset_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    (**self).get_headset_battery_information(user_index)
+  }
+}
+
+impl<B: ControllerBackend + ?Sized> ControllerBackend for Arc<B> {
+  fn get_state(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    (**self).get_state(user_index)
+  }
+
+  fn get_state_ex(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    (**self).get_state_ex(user_index)
+  }
+
+  fn set_state(
+    &self,
+    user_index: u32,
+    left_motor_speed: u16,
+    right_motor_speed: u16,
+  ) -> Result<(), XInputUsageError> {
+    (**self).set_state(user_index, left_motor_speed, right_motor_speed)
+  }
+
+  fn get_capabilities(&self, user_index: u32) -> Result<XInputCapabilities, XInputUsageError> {
+    (**self).get_capabilities(user_index)
+  }
+
+  fn get_keystroke(
+    &self,
+    user_index: u32,
+  ) -> Result<Option<XINPUT_KEYSTROKE>, XInputOptionalFnUsageError> {
+    (**self).get_keystroke(user_index)
+  }
+
+  fn get_gamepad_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    (**self).get_gamepad_battery_information(user_index)
+  }
+
+  fn get_headset_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    (**self).get_headset_battery_information(user_index)
+  }
+}
+
+/// What a plain wired gamepad with all inputs and full rumble reports.
+pub(crate) fn wired_gamepad_capabilities() -> XINPUT_CAPABILITIES {
+  XINPUT_CAPABILITIES {
+    Type: XINPUT_DEVTYPE_GAMEPAD,
+    SubType: XINPUT_DEVSUBTYPE_GAMEPAD,
+    Flags: 0,
+    Gamepad: XINPUT_GAMEPAD {
+      wButtons: 0xFFFF,
+      bLeftTrigger: 0xFF,
+      bRightTrigger: 0xFF,
+      sThumbLX: -64,
+      sThumbLY: -64,
+      sThumbRX: -64,
+      sThumbRY: -64,
+    },
+    Vibration: XINPUT_VIBRATION {
+      wLeftMotorSpeed: 0xFFFF,
+      wRightMotorSpeed: 0xFFFF,
+    },
+  }
+}
+
+/// The data for one controller slot of a `MockBackend`.
+struct MockSlot {
+  connected: bool,
+  state: XINPUT_STATE,
+  capabilities: XINPUT_CAPABILITIES,
+  vibration: (u16, u16),
+  keystrokes: VecDeque<XINPUT_KEYSTROKE>,
+  gamepad_battery: XInputBatteryInformation,
+  headset_battery: XInputBatteryInformation,
+}
+
+impl MockSlot {
+  fn new() -> Self {
+    MockSlot {
+      connected: false,
+      state: XInputState::default().raw,
+      capabilities: wired_gamepad_capabilities(),
+      vibration: (0, 0),
+      keystrokes: VecDeque::new(),
+      gamepad_battery: XInputBatteryInformation {
+        battery_type: BatteryType::WIRED,
+        battery_level: BatteryLevel::FULL,
+      },
+      headset_battery: XInputBatteryInformation {
+        battery_type: BatteryType::DISCONNECTED,
+        battery_level: BatteryLevel::EMPTY,
+      },
+    }
+  }
+
+  /// Every change to the gamepad data gets a new packet number, just like with
+  /// a real controller.
+  fn touch(&mut self) {
+    self.state.dwPacketNumber = self.state.dwPacketNumber.wrapping_add(1);
+  }
+}
+
+/// An in-memory `ControllerBackend` that you control from your own code.
+///
+/// All four slots start out disconnected. You can plug controllers in and out,
+/// press buttons, move sticks, and so on, and then whatever code you're testing
+/// will see those changes the next time it polls the backend. Every change to a
+/// connected slot's gamepad data advances its packet number.
+///
+/// All the scripting methods take `&self`, so you can keep a reference to the
+/// mock while the code under test also holds one.
+///
+/// # Panics
+///
+/// The scripting methods panic if you give a `user_index` of 4 or more. The
+/// `ControllerBackend` methods return `InvalidControllerID` instead, the same
+/// as with a real `XInputHandle`.
+pub struct MockBackend {
+  slots: Mutex<[MockSlot; 4]>,
+}
+
+impl Debug for MockBackend {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    let slots = self.lock();
+    write!(
+      f,
+      "MockBackend(connected = [{}, {}, {}, {}])",
+      slots[0].connected, slots[1].connected, slots[2].connected, slots[3].connected
+    )
+  }
+}
+
+impl Default for MockBackend {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl MockBackend {
+  /// Makes a new mock with all four slots disconnected.
+  pub fn new() -> Self {
+    MockBackend {
+      slots: Mutex::new([MockSlot::new(), MockSlot::new(), MockSlot::new(), MockSlot::new()]),
+    }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, [MockSlot; 4]> {
+    // A panic in some other test thread shouldn't make the mock unusable.
+    self
+      .slots
+      .lock()
+      .unwrap_or_else(|poison| poison.into_inner())
+  }
+
+  fn with_slot<T, F: FnOnce(&mut MockSlot) -> T>(&self, user_index: u32, f: F) -> T {
+    assert!(user_index < 4, "Invalid user_index: {}", user_index);
+    f(&mut self.lock()[user_index as usize])
+  }
+
+  /// Plugs in a controller. The gamepad data is left as it was.
+  pub fn connect(&self, user_index: u32) {
+    self.with_slot(user_index, |slot| {
+      if !slot.connected {
+        slot.connected = true;
+        slot.touch();
+      }
+    })
+  }
+
+  /// Unplugs a controller. Any pending keystrokes are lost.
+  pub fn disconnect(&self, user_index: u32) {
+    self.with_slot(user_index, |slot| {
+      slot.connected = false;
+      slot.keystrokes.clear();
+    })
+  }
+
+  /// If the slot currently has a controller plugged in.
+  pub fn is_connected(&self, user_index: u32) -> bool {
+    self.with_slot(user_index, |slot| slot.connected)
+  }
+
+  /// Replaces the whole gamepad state of a slot.
+  pub fn set_gamepad(&self, user_index: u32, gamepad: XINPUT_GAMEPAD) {
+    self.update_gamepad(user_index, |g| *g = gamepad)
+  }
+
+  /// Edits the gamepad state of a slot in place.
+  pub fn update_gamepad<F: FnOnce(&mut XINPUT_GAMEPAD)>(&self, user_index: u32, f: F) {
+    self.with_slot(user_index, |slot| {
+      f(&mut slot.state.Gamepad);
+      slot.touch();
+    })
+  }
+
+  /// Presses all buttons in the `XINPUT_GAMEPAD_*` bit mask given.
+  pub fn press_buttons(&self, user_index: u32, buttons: u16) {
+    self.update_gamepad(user_index, |g| g.wButtons |= buttons)
+  }
+
+  /// Releases all buttons in the `XINPUT_GAMEPAD_*` bit mask given.
+  pub fn release_buttons(&self, user_index: u32, buttons: u16) {
+    self.update_gamepad(user_index, |g| g.wButtons &= !buttons)
+  }
+
+  /// Sets the raw position of the left stick.
+  pub fn set_left_stick(&self, user_index: u32, x: i16, y: i16) {
+    self.update_gamepad(user_index, |g| {
+      g.sThumbLX = x;
+      g.sThumbLY = y;
+    })
+  }
+
+  /// Sets the raw position of the right stick.
+  pub fn set_right_stick(&self, user_index: u32, x: i16, y: i16) {
+    self.update_gamepad(user_index, |g| {
+      g.sThumbRX = x;
+      g.sThumbRY = y;
+    })
+  }
+
+  /// Sets the raw values of both triggers.
+  pub fn set_triggers(&self, user_index: u32, left: u8, right: u8) {
+    self.update_gamepad(user_index, |g| {
+      g.bLeftTrigger = left;
+      g.bRightTrigger = right;
+    })
+  }
+
+  /// Sets the capabilities that `get_capabilities` will report.
+  ///
+  /// By default each slot reports a wired gamepad with full rumble.
+  pub fn set_capabilities(&self, user_index: u32, capabilities: XINPUT_CAPABILITIES) {
+    self.with_slot(user_index, |slot| slot.capabilities = capabilities)
+  }
+
+  /// Queues up a keystroke for `get_keystroke` to return.
+  pub fn push_keystroke(&self, user_index: u32, keystroke: XINPUT_KEYSTROKE) {
+    self.with_slot(user_index, |slot| slot.keystrokes.push_back(keystroke))
+  }
+
+  /// Sets the gamepad battery info. The default is `WIRED` and `FULL`.
+  pub fn set_gamepad_battery(&self, user_index: u32, info: XInputBatteryInformation) {
+    self.with_slot(user_index, |slot| slot.gamepad_battery = info)
+  }
+
+  /// Sets the headset battery info. The default is `DISCONNECTED` and `EMPTY`.
+  pub fn set_headset_battery(&self, user_index: u32, info: XInputBatteryInformation) {
+    self.with_slot(user_index, |slot| slot.headset_battery = info)
+  }
+
+  /// The motor speeds from the last successful `set_state` call on this slot.
+  pub fn vibration(&self, user_index: u32) -> (u16, u16) {
+    self.with_slot(user_index, |slot| slot.vibration)
+  }
+
+  /// Runs `f` on a connected slot, or gives the same errors XInput would.
+  fn connected_slot<T, F: FnOnce(&mut MockSlot) -> T>(
+    &self,
+    user_index: u32,
+    f: F,
+  ) -> Result<T, XInputUsageError> {
+    if user_index >= 4 {
+      Err(XInputUsageError::InvalidControllerID)
+    } else {
+      let slot = &mut self.lock()[user_index as usize];
+      if slot.connected {
+        Ok(f(slot))
+      } else {
+        Err(XInputUsageError::DeviceNotConnected)
+      }
+    }
+  }
+}
+
+impl ControllerBackend for MockBackend {
+  fn get_state(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    // Plain `XInputGetState` never reports the guide button.
+    self.connected_slot(user_index, |slot| {
+      let mut raw = slot.state;
+      raw.Gamepad.wButtons &= !XINPUT_GAMEPAD_GUIDE;
+      XInputState { raw }
+    })
+  }
+
+  fn get_state_ex(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    self.connected_slot(user_index, |slot| XInputState { raw: slot.state })
+  }
+
+  fn set_state(
+    &self,
+    user_index: u32,
+    left_motor_speed: u16,
+    right_motor_speed: u16,
+  ) -> Result<(), XInputUsageError> {
+    self.connected_slot(user_index, |slot| {
+      slot.vibration = (left_motor_speed, right_motor_speed);
+    })
+  }
+
+  fn get_capabilities(&self, user_index: u32) -> Result<XInputCapabilities, XInputUsageError> {
+    self.connected_slot(user_index, |slot| {
+      XInputCapabilities::from(slot.capabilities)
+    })
+  }
+
+  fn get_keystroke(
+    &self,
+    user_index: u32,
+  ) -> Result<Option<XINPUT_KEYSTROKE>, XInputOptionalFnUsageError> {
+    self
+      .connected_slot(user_index, |slot| slot.keystrokes.pop_front())
+      .map_err(|e| match e {
+        XInputUsageError::InvalidControllerID => XInputOptionalFnUsageError::InvalidControllerID,
+        _ => XInputOptionalFnUsageError::DeviceNotConnected,
+      })
+  }
+
+  fn get_gamepad_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    if user_index >= 4 {
+      Err(XInputOptionalFnUsageError::InvalidControllerID)
+    } else {
+      self.with_slot(user_index, |slot| {
+        Ok(if slot.connected {
+          slot.gamepad_battery
+        } else {
+          XInputBatteryInformation {
+            battery_type: BatteryType::DISCONNECTED,
+            battery_level: BatteryLevel::EMPTY,
+          }
+        })
+      })
+    }
+  }
+
+  fn get_headset_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    if user_index >= 4 {
+      Err(XInputOptionalFnUsageError::InvalidControllerID)
+    } else {
+      self.with_slot(user_index, |slot| {
+        Ok(if slot.connected {
+          slot.headset_battery
+        } else {
+          XInputBatteryInformation {
+            battery_type: BatteryType::DISCONNECTED,
+            battery_level: BatteryLevel::EMPTY,
+          }
+        })
+      })
+    }
+  }
+}
+
+#[test]
+fn mock_backend_test() {
+  use super::XINPUT_GAMEPAD_A;
+
+  let mock = MockBackend::new();
+  assert_eq!(mock.get_state(0), Err(XInputUsageError::DeviceNotConnected));
+  assert_eq!(
+    mock.get_state(4),
+    Err(XInputUsageError::InvalidControllerID)
+  );
+
+  mock.connect(0);
+  let first = mock.get_state(0).unwrap();
+  mock.press_buttons(0, XINPUT_GAMEPAD_A | XINPUT_GAMEPAD_GUIDE);
+  let second = mock.get_state(0).unwrap();
+  assert!(first != second);
+  assert!(second.south_button());
+  assert!(!second.guide_button());
+  assert!(mock.get_state_ex(0).unwrap().guide_button());
+
+  assert_eq!(mock.set_state(0, 10, 20), Ok(()));
+  assert_eq!(mock.vibration(0), (10, 20));
+
+  mock.disconnect(0);
+  assert_eq!(mock.get_state(0), Err(XInputUsageError::DeviceNotConnected));
+  assert_eq!(
+    mock.get_keystroke(0),
+    Err(XInputOptionalFnUsageError::DeviceNotConnected)
+  );
+}
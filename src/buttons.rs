@@ -0,0 +1,390 @@
+//! Sets of buttons as a single value.
+
+use std::fmt::{self, Debug, Formatter};
+use std::iter::FromIterator;
+use std::ops::{
+  BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Sub, SubAssign,
+};
+
+use super::{
+  XINPUT_GAMEPAD_A, XINPUT_GAMEPAD_B, XINPUT_GAMEPAD_BACK, XINPUT_GAMEPAD_DPAD_DOWN,
+  XINPUT_GAMEPAD_DPAD_LEFT, XINPUT_GAMEPAD_DPAD_RIGHT, XINPUT_GAMEPAD_DPAD_UP,
+  XINPUT_GAMEPAD_GUIDE, XINPUT_GAMEPAD_LEFT_SHOULDER, XINPUT_GAMEPAD_LEFT_THUMB,
+  XINPUT_GAMEPAD_RIGHT_SHOULDER, XINPUT_GAMEPAD_RIGHT_THUMB, XINPUT_GAMEPAD_START,
+  XINPUT_GAMEPAD_X, XINPUT_GAMEPAD_Y,
+};
+
+/// One of the digital buttons of a controller.
+///
+/// The variants use the same names as the `XInputState` button methods, see
+/// those for what each company calls each button.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Button {
+  /// The D-pad up direction.
+  ArrowUp,
+  /// The D-pad down direction.
+  ArrowDown,
+  /// The D-pad left direction.
+  ArrowLeft,
+  /// The D-pad right direction.
+  ArrowRight,
+  /// The start button.
+  Start,
+  /// The select button (XBox: Back).
+  Select,
+  /// Pressing down on the left stick.
+  LeftThumb,
+  /// Pressing down on the right stick.
+  RightThumb,
+  /// The left shoulder button.
+  LeftShoulder,
+  /// The right shoulder button.
+  RightShoulder,
+  /// The guide button. Only `get_state_ex` ever reports this one.
+  Guide,
+  /// The south action button (XBox: A).
+  South,
+  /// The east action button (XBox: B).
+  East,
+  /// The west action button (XBox: X).
+  West,
+  /// The north action button (XBox: Y).
+  North,
+}
+
+impl Button {
+  /// Every button, in the order of their bits in `wButtons`.
+  pub const ALL: [Button; 15] = [
+    Button::ArrowUp,
+    Button::ArrowDown,
+    Button::ArrowLeft,
+    Button::ArrowRight,
+    Button::Start,
+    Button::Select,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::LeftShoulder,
+    Button::RightShoulder,
+    Button::Guide,
+    Button::South,
+    Button::East,
+    Button::West,
+    Button::North,
+  ];
+
+  /// The bit for this button in `wButtons`.
+  #[inline]
+  pub fn mask(self) -> u16 {
+    match self {
+      Button::ArrowUp => XINPUT_GAMEPAD_DPAD_UP,
+      Button::ArrowDown => XINPUT_GAMEPAD_DPAD_DOWN,
+      Button::ArrowLeft => XINPUT_GAMEPAD_DPAD_LEFT,
+      Button::ArrowRight => XINPUT_GAMEPAD_DPAD_RIGHT,
+      Button::Start => XINPUT_GAMEPAD_START,
+      Button::Select => XINPUT_GAMEPAD_BACK,
+      Button::LeftThumb => XINPUT_GAMEPAD_LEFT_THUMB,
+      Button::RightThumb => XINPUT_GAMEPAD_RIGHT_THUMB,
+      Button::LeftShoulder => XINPUT_GAMEPAD_LEFT_SHOULDER,
+      Button::RightShoulder => XINPUT_GAMEPAD_RIGHT_SHOULDER,
+      Button::Guide => XINPUT_GAMEPAD_GUIDE,
+      Button::South => XINPUT_GAMEPAD_A,
+      Button::East => XINPUT_GAMEPAD_B,
+      Button::West => XINPUT_GAMEPAD_X,
+      Button::North => XINPUT_GAMEPAD_Y,
+    }
+  }
+}
+
+/// A set of buttons, stored the same way as the `wButtons` field.
+///
+/// Bits that don't belong to any `Button` are dropped when you convert from a
+/// `u16`, so two sets with the same buttons are always equal. If you fill in
+/// the field yourself, stick to the bits of `Buttons::ALL`.
+#[derive(Default, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct Buttons(pub u16);
+
+impl Buttons {
+  /// No buttons at all.
+  pub const NONE: Self = Buttons(0);
+
+  /// Every button that a `Button` can name.
+  pub const ALL: Self = Buttons(
+    XINPUT_GAMEPAD_DPAD_UP
+      | XINPUT_GAMEPAD_DPAD_DOWN
+      | XINPUT_GAMEPAD_DPAD_LEFT
+      | XINPUT_GAMEPAD_DPAD_RIGHT
+      | XINPUT_GAMEPAD_START
+      | XINPUT_GAMEPAD_BACK
+      | XINPUT_GAMEPAD_LEFT_THUMB
+      | XINPUT_GAMEPAD_RIGHT_THUMB
+      | XINPUT_GAMEPAD_LEFT_SHOULDER
+      | XINPUT_GAMEPAD_RIGHT_SHOULDER
+      | XINPUT_GAMEPAD_GUIDE
+      | XINPUT_GAMEPAD_A
+      | XINPUT_GAMEPAD_B
+      | XINPUT_GAMEPAD_X
+      | XINPUT_GAMEPAD_Y,
+  );
+
+  /// The four D-pad directions.
+  pub const DPAD: Self = Buttons(
+    XINPUT_GAMEPAD_DPAD_UP
+      | XINPUT_GAMEPAD_DPAD_DOWN
+      | XINPUT_GAMEPAD_DPAD_LEFT
+      | XINPUT_GAMEPAD_DPAD_RIGHT,
+  );
+
+  /// The four action buttons.
+  pub const ACTION: Self =
+    Buttons(XINPUT_GAMEPAD_A | XINPUT_GAMEPAD_B | XINPUT_GAMEPAD_X | XINPUT_GAMEPAD_Y);
+
+  /// If no buttons are in the set.
+  #[inline]
+  pub fn is_empty(self) -> bool {
+    self.0 & Self::ALL.0 == 0
+  }
+
+  /// How many buttons are in the set.
+  #[inline]
+  pub fn len(self) -> usize {
+    (self.0 & Self::ALL.0).count_ones() as usize
+  }
+
+  /// If the button given is in the set.
+  #[inline]
+  pub fn contains(self, button: Button) -> bool {
+    self.0 & button.mask() != 0
+  }
+
+  /// If every button of `other` is also in this set.
+  #[inline]
+  pub fn contains_all(self, other: Buttons) -> bool {
+    self.0 & other.0 == other.0
+  }
+
+  /// If any button of `other` is also in this set.
+  #[inline]
+  pub fn intersects(self, other: Buttons) -> bool {
+    self.0 & other.0 != 0
+  }
+
+  /// Adds a button to the set.
+  #[inline]
+  pub fn insert(&mut self, button: Button) {
+    self.0 |= button.mask();
+  }
+
+  /// Takes a button out of the set.
+  #[inline]
+  pub fn remove(&mut self, button: Button) {
+    self.0 &= !button.mask();
+  }
+
+  /// The buttons in either set.
+  #[inline]
+  pub fn union(self, other: Buttons) -> Buttons {
+    Buttons(self.0 | other.0)
+  }
+
+  /// The buttons in both sets.
+  #[inline]
+  pub fn intersection(self, other: Buttons) -> Buttons {
+    Buttons(self.0 & other.0)
+  }
+
+  /// The buttons in this set that aren't in `other`.
+  #[inline]
+  pub fn difference(self, other: Buttons) -> Buttons {
+    Buttons(self.0 & !other.0)
+  }
+
+  /// The buttons in exactly one of the two sets.
+  #[inline]
+  pub fn symmetric_difference(self, other: Buttons) -> Buttons {
+    Buttons(self.0 ^ other.0)
+  }
+
+  /// Iterates over the buttons in the set, in the order of `Button::ALL`.
+  #[inline]
+  pub fn iter(self) -> ButtonsIter {
+    ButtonsIter {
+      buttons: self,
+      next: 0,
+    }
+  }
+}
+
+impl Debug for Buttons {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    let unknown = self.0 & !Self::ALL.0;
+    if unknown == 0 {
+      f.debug_set().entries(self.iter()).finish()
+    } else {
+      f.debug_set()
+        .entries(self.iter())
+        .entry(&format_args!("{:#06X}", unknown))
+        .finish()
+    }
+  }
+}
+
+impl From<u16> for Buttons {
+  #[inline]
+  fn from(w_buttons: u16) -> Self {
+    Buttons(w_buttons & Self::ALL.0)
+  }
+}
+
+impl From<Buttons> for u16 {
+  #[inline]
+  fn from(buttons: Buttons) -> Self {
+    buttons.0
+  }
+}
+
+impl From<Button> for Buttons {
+  #[inline]
+  fn from(button: Button) -> Self {
+    Buttons(button.mask())
+  }
+}
+
+impl FromIterator<Button> for Buttons {
+  fn from_iter<I: IntoIterator<Item = Button>>(iter: I) -> Self {
+    let mut buttons = Buttons::NONE;
+    buttons.extend(iter);
+    buttons
+  }
+}
+
+impl Extend<Button> for Buttons {
+  fn extend<I: IntoIterator<Item = Button>>(&mut self, iter: I) {
+    for button in iter {
+      self.insert(button);
+    }
+  }
+}
+
+impl IntoIterator for Buttons {
+  type Item = Button;
+  type IntoIter = ButtonsIter;
+  #[inline]
+  fn into_iter(self) -> ButtonsIter {
+    self.iter()
+  }
+}
+
+/// Iterator over the buttons of a `Buttons` set.
+#[derive(Debug, Clone)]
+pub struct ButtonsIter {
+  buttons: Buttons,
+  next: usize,
+}
+
+impl Iterator for ButtonsIter {
+  type Item = Button;
+  fn next(&mut self) -> Option<Button> {
+    while let Some(&button) = Button::ALL.get(self.next) {
+      self.next += 1;
+      if self.buttons.contains(button) {
+        return Some(button);
+      }
+    }
+    None
+  }
+}
+
+macro_rules! impl_set_op {
+  ($op:ident, $method:ident, $assign_op:ident, $assign_method:ident, $set_method:ident) => {
+    impl $op for Buttons {
+      type Output = Buttons;
+      #[inline]
+      fn $method(self, other: Buttons) -> Buttons {
+        self.$set_method(other)
+      }
+    }
+    impl $op<Button> for Buttons {
+      type Output = Buttons;
+      #[inline]
+      fn $method(self, other: Button) -> Buttons {
+        self.$set_method(Buttons::from(other))
+      }
+    }
+    impl $assign_op for Buttons {
+      #[inline]
+      fn $assign_method(&mut self, other: Buttons) {
+        *self = self.$set_method(other);
+      }
+    }
+    impl $assign_op<Button> for Buttons {
+      #[inline]
+      fn $assign_method(&mut self, other: Button) {
+        *self = self.$set_method(Buttons::from(other));
+      }
+    }
+  };
+}
+
+impl_set_op!(BitOr, bitor, BitOrAssign, bitor_assign, union);
+impl_set_op!(BitAnd, bitand, BitAndAssign, bitand_assign, intersection);
+impl_set_op!(Sub, sub, SubAssign, sub_assign, difference);
+impl_set_op!(
+  BitXor,
+  bitxor,
+  BitXorAssign,
+  bitxor_assign,
+  symmetric_difference
+);
+
+impl BitOr for Button {
+  type Output = Buttons;
+  #[inline]
+  fn bitor(self, other: Button) -> Buttons {
+    Buttons::from(self) | other
+  }
+}
+
+impl Not for Buttons {
+  type Output = Buttons;
+  /// Every known button that isn't in this set.
+  #[inline]
+  fn not(self) -> Buttons {
+    Buttons(!self.0 & Self::ALL.0)
+  }
+}
+
+#[test]
+fn buttons_test() {
+  let raw = XINPUT_GAMEPAD_A | XINPUT_GAMEPAD_GUIDE | XINPUT_GAMEPAD_DPAD_LEFT;
+  let buttons = Buttons::from(raw);
+  assert_eq!(u16::from(buttons), raw);
+  assert_eq!(buttons.len(), 3);
+  assert_eq!(
+    buttons.iter().collect::<Vec<_>>(),
+    vec![Button::ArrowLeft, Button::Guide, Button::South]
+  );
+  assert_eq!(Button::South | Button::Guide | Button::ArrowLeft, buttons);
+
+  let chord = Button::South | Button::East;
+  assert!(buttons.intersects(chord) && !buttons.contains_all(chord));
+  assert_eq!(buttons & chord, Buttons::from(Button::South));
+  assert_eq!(buttons - Button::Guide, Button::South | Button::ArrowLeft);
+  assert_eq!(
+    buttons ^ chord,
+    Button::East | Button::Guide | Button::ArrowLeft
+  );
+  assert_eq!((!buttons).len(), 12);
+  assert!((buttons - buttons).is_empty());
+
+  for &button in Button::ALL.iter() {
+    assert!(Buttons::ALL.contains(button));
+    assert_eq!(Buttons::from(button).iter().next(), Some(button));
+  }
+
+  // unknown bits aren't buttons, so they're dropped
+  let odd = Buttons::from(0x0800 | XINPUT_GAMEPAD_START);
+  assert_eq!(odd, Buttons::from(Button::Start));
+  assert_eq!(u16::from(odd), XINPUT_GAMEPAD_START);
+  assert_eq!(Buttons::from(0x0800), Buttons::NONE);
+  assert!(Buttons::from(0x0800).is_empty());
+}
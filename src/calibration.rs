@@ -0,0 +1,327 @@
+//! Correcting sticks that drift or don't reach the edges.
+
+use super::XInputState;
+
+/// A correction for one stick, made by a `StickCalibrator`.
+///
+/// `apply` moves the resting position back to `(0, 0)` and stretches each
+/// direction so that the most extreme position that was seen becomes the
+/// largest raw value. The output is still a raw stick value, so it should go
+/// through a deadzone afterwards, the same as an uncalibrated value.
+///
+/// With the `serde` feature enabled this can be serialized, so you can save
+/// the calibration for each controller.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct StickCalibration {
+  /// The raw value of the stick when it's resting.
+  pub center: (i16, i16),
+  /// The lowest raw value that the stick reaches on each axis.
+  pub min: (i16, i16),
+  /// The highest raw value that the stick reaches on each axis.
+  pub max: (i16, i16),
+}
+
+impl StickCalibration {
+  /// A calibration that doesn't change anything (other than moving -32,768 to
+  /// -32,767).
+  pub const IDENTITY: Self = StickCalibration {
+    center: (0, 0),
+    min: (-i16::MAX, -i16::MAX),
+    max: (i16::MAX, i16::MAX),
+  };
+
+  /// Applies the calibration to a raw stick value.
+  pub fn apply(&self, raw_stick: (i16, i16)) -> (i16, i16) {
+    (
+      calibrate_axis(raw_stick.0, self.center.0, self.min.0, self.max.0),
+      calibrate_axis(raw_stick.1, self.center.1, self.min.1, self.max.1),
+    )
+  }
+}
+
+impl Default for StickCalibration {
+  /// `IDENTITY`
+  fn default() -> Self {
+    StickCalibration::IDENTITY
+  }
+}
+
+fn calibrate_axis(value: i16, center: i16, min: i16, max: i16) -> i16 {
+  let value = i32::from(value);
+  let center = i32::from(center);
+  let span = if value >= center {
+    i32::from(max) - center
+  } else {
+    center - i32::from(min)
+  };
+  let out = (value - center) * i32::from(i16::MAX) / span.max(1);
+  out.clamp(-i32::from(i16::MAX), i32::from(i16::MAX)) as i16
+}
+
+/// Watches raw stick values and works out a `StickCalibration` for them.
+///
+/// Feed it every raw value of one stick with `observe`, and call `profile`
+/// whenever you want the current calibration.
+///
+/// * The center is the average of the values seen while the stick is resting.
+///   A value counts as resting when it's within `rest_radius` of the current
+///   center estimate and it moved no more than `stillness` since the last
+///   value. That way a stick being swept across the middle doesn't pull the
+///   center around. The average is a moving one, so the center keeps up if
+///   the drift changes during play.
+/// * The first estimate is `(0, 0)`, which a stick that drifts further than
+///   `rest_radius` would never be near. So until the first resting value is
+///   found, `SETTLE_SAMPLES` still values in a row also count as resting, as
+///   long as they're within `MAX_SETTLED_DRIFT` of `(0, 0)`. A stick that's
+///   held at a tilt when the program starts is still, but it isn't resting.
+/// * The extent in each direction is the furthest value seen that way. Until
+///   the stick has been pushed at least `min_extent` away from the center in a
+///   direction, that direction uses the full raw range, so a stick that hasn't
+///   moved yet isn't stretched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StickCalibrator {
+  rest_radius: i16,
+  stillness: i16,
+  min_extent: i16,
+  smoothing: f32,
+  center: (f32, f32),
+  min: (i16, i16),
+  max: (i16, i16),
+  last: Option<(i16, i16)>,
+  still_run: u32,
+  samples: u64,
+  resting_samples: u64,
+}
+
+impl Default for StickCalibrator {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl StickCalibrator {
+  /// How many still values in a row count as resting before any resting
+  /// value has been found.
+  pub const SETTLE_SAMPLES: u32 = 30;
+
+  /// How far from `(0, 0)` the still values can be when they're counted as
+  /// resting that way. Drift past this is very rare, but a stick being held
+  /// over to one side isn't.
+  pub const MAX_SETTLED_DRIFT: i16 = 16_384;
+
+  /// A calibrator with the default settings and no samples.
+  ///
+  /// * `rest_radius`: `XInputState::LEFT_STICK_DEADZONE`
+  /// * `stillness`: 512
+  /// * `min_extent`: 16,384
+  /// * `smoothing`: 0.05
+  pub fn new() -> Self {
+    StickCalibrator {
+      rest_radius: XInputState::LEFT_STICK_DEADZONE,
+      stillness: 512,
+      min_extent: 16_384,
+      smoothing: 0.05,
+      center: (0.0, 0.0),
+      min: (0, 0),
+      max: (0, 0),
+      last: None,
+      still_run: 0,
+      samples: 0,
+      resting_samples: 0,
+    }
+  }
+
+  /// Sets how far from the center estimate a value can be and still count as
+  /// resting.
+  pub fn with_rest_radius(mut self, rest_radius: i16) -> Self {
+    self.rest_radius = rest_radius.max(0);
+    self
+  }
+
+  /// Sets how far the stick can move between two values and still count as
+  /// resting.
+  pub fn with_stillness(mut self, stillness: i16) -> Self {
+    self.stillness = stillness.max(0);
+    self
+  }
+
+  /// Sets how far from the center the stick has to go before the extent in
+  /// that direction is used.
+  pub fn with_min_extent(mut self, min_extent: i16) -> Self {
+    self.min_extent = min_extent.max(1);
+    self
+  }
+
+  /// Sets how quickly the center estimate follows new resting values, from
+  /// 0.0 (never) to 1.0 (it's always the latest resting value).
+  pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+    self.smoothing = smoothing.clamp(0.0, 1.0);
+    self
+  }
+
+  /// Starts over, forgetting every value seen so far but keeping the settings.
+  pub fn reset(&mut self) {
+    *self = StickCalibrator {
+      rest_radius: self.rest_radius,
+      stillness: self.stillness,
+      min_extent: self.min_extent,
+      smoothing: self.smoothing,
+      ..StickCalibrator::new()
+    };
+  }
+
+  /// Feeds in a new raw stick value.
+  pub fn observe(&mut self, raw_stick: (i16, i16)) {
+    let (x, y) = raw_stick;
+    if self.samples == 0 {
+      self.min = raw_stick;
+      self.max = raw_stick;
+    } else {
+      self.min = (self.min.0.min(x), self.min.1.min(y));
+      self.max = (self.max.0.max(x), self.max.1.max(y));
+    }
+    self.samples += 1;
+
+    let still = match self.last {
+      Some((last_x, last_y)) => {
+        (i32::from(x) - i32::from(last_x)).abs() <= i32::from(self.stillness)
+          && (i32::from(y) - i32::from(last_y)).abs() <= i32::from(self.stillness)
+      }
+      None => false,
+    };
+    self.last = Some(raw_stick);
+    self.still_run = if still { self.still_run + 1 } else { 0 };
+    let from_zero = (f32::from(x).powi(2) + f32::from(y).powi(2)).sqrt();
+    let settled = self.resting_samples == 0
+      && self.still_run >= Self::SETTLE_SAMPLES
+      && from_zero <= f32::from(Self::MAX_SETTLED_DRIFT);
+    let dx = f32::from(x) - self.center.0;
+    let dy = f32::from(y) - self.center.1;
+    let near_center = (dx * dx + dy * dy).sqrt() <= f32::from(self.rest_radius);
+    if still && (near_center || settled) {
+      // the first resting value is taken as is, after that we smooth.
+      let rate = if self.resting_samples == 0 {
+        1.0
+      } else {
+        self.smoothing
+      };
+      self.center.0 += dx * rate;
+      self.center.1 += dy * rate;
+      self.resting_samples += 1;
+    }
+  }
+
+  /// How many values have been seen.
+  pub fn sample_count(&self) -> u64 {
+    self.samples
+  }
+
+  /// How many of the values seen counted as resting.
+  pub fn resting_sample_count(&self) -> u64 {
+    self.resting_samples
+  }
+
+  /// The calibration for everything seen so far.
+  pub fn profile(&self) -> StickCalibration {
+    let center = (self.center.0.round() as i16, self.center.1.round() as i16);
+    let min_extent = i32::from(self.min_extent);
+    let min = |seen: i16, center: i16| {
+      if i32::from(center) - i32::from(seen) >= min_extent {
+        seen
+      } else {
+        -i16::MAX
+      }
+    };
+    let max = |seen: i16, center: i16| {
+      if i32::from(seen) - i32::from(center) >= min_extent {
+        seen
+      } else {
+        i16::MAX
+      }
+    };
+    StickCalibration {
+      center,
+      min: (min(self.min.0, center.0), min(self.min.1, center.1)),
+      max: (max(self.max.0, center.0), max(self.max.1, center.1)),
+    }
+  }
+}
+
+#[test]
+fn stick_calibrator_test() {
+  assert_eq!(
+    StickCalibration::IDENTITY.apply((1234, -5678)),
+    (1234, -5678)
+  );
+  assert_eq!(
+    StickCalibration::IDENTITY.apply((i16::MIN, i16::MAX)),
+    (-i16::MAX, i16::MAX)
+  );
+
+  // a stick that rests at (1500, -800), and only reaches 25,000 to the right
+  let mut calibrator = StickCalibrator::new();
+  for i in 0..200 {
+    let jitter = (i % 5) as i16 * 20 - 40;
+    calibrator.observe((1500 + jitter, -800 - jitter));
+  }
+  let rested = calibrator.profile();
+  assert!((i32::from(rested.center.0) - 1500).abs() <= 40);
+  assert!((i32::from(rested.center.1) + 800).abs() <= 40);
+  // nowhere near the edges yet, so no stretching
+  assert_eq!(rested.max, (i16::MAX, i16::MAX));
+  assert_eq!(rested.min, (-i16::MAX, -i16::MAX));
+
+  // a fast sweep through the middle doesn't count as resting
+  let resting = calibrator.resting_sample_count();
+  for &x in &[-20_000, -5000, 1500, 9000, 25_000] {
+    calibrator.observe((x, -800));
+  }
+  assert_eq!(calibrator.resting_sample_count(), resting);
+  let profile = calibrator.profile();
+  assert_eq!(profile.max.0, 25_000);
+  assert_eq!(profile.min.0, -20_000);
+  assert_eq!(profile.max.1, i16::MAX);
+  assert_eq!(profile.apply(profile.center), (0, 0));
+  assert_eq!(profile.apply((25_000, -800)).0, i16::MAX);
+  assert_eq!(profile.apply((-20_000, -800)).0, -i16::MAX);
+  assert_eq!(profile.apply((30_000, -800)).0, i16::MAX);
+
+  // drift past the deadzone is found once the stick has settled
+  for &drift in &[8000, 9000, -15_000] {
+    let mut calibrator = StickCalibrator::new();
+    for i in 0..100 {
+      let jitter = (i % 5) as i16 * 20 - 40;
+      calibrator.observe((drift + jitter, jitter));
+    }
+    assert!(calibrator.resting_sample_count() > 0);
+    let profile = calibrator.profile();
+    assert!((i32::from(profile.center.0) - i32::from(drift)).abs() <= 40);
+    let normalized = XInputState::normalize_raw_stick_value(
+      profile.apply((drift, 0)),
+      XInputState::LEFT_STICK_DEADZONE,
+    );
+    assert_eq!(normalized, (0.0, 0.0));
+  }
+
+  // held at a tilt on startup isn't resting, and letting go finds the center
+  let mut calibrator = StickCalibrator::new();
+  for i in 0..60 {
+    let jitter = (i % 5) as i16 * 20 - 40;
+    calibrator.observe((30_000 + jitter, jitter));
+  }
+  assert_eq!(calibrator.resting_sample_count(), 0);
+  for i in 0..100 {
+    let jitter = (i % 5) as i16 * 20 - 40;
+    calibrator.observe((300 + jitter, jitter));
+  }
+  assert!(calibrator.resting_sample_count() > 0);
+  let profile = calibrator.profile();
+  assert!((i32::from(profile.center.0) - 300).abs() <= 40);
+  assert!(i32::from(profile.center.1).abs() <= 40);
+  assert_eq!(profile.max.0, 30_040);
+
+  calibrator.reset();
+  assert_eq!(calibrator.sample_count(), 0);
+  assert_eq!(calibrator.profile(), StickCalibration::IDENTITY);
+}
@@ -0,0 +1,272 @@
+//! Shaping analog values after the deadzone is removed.
+
+/// A curve that maps an analog magnitude from 0.0 to 1.0 onto 0.0 to 1.0.
+///
+/// Every curve maps 0.0 to 0.0 and 1.0 to 1.0 (a lookup table does if you
+/// give it those points), it's only the in between values that differ. Inputs
+/// are clamped to the range 0.0 to 1.0 before use, and so are outputs.
+///
+/// With the `serde` feature enabled this can be serialized, so it can go in
+/// your settings file.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum ResponseCurve {
+  /// The output is the input.
+  Linear,
+  /// `x.powf(exponent)`. Exponents above 1.0 give more precision near the
+  /// center (good for aiming), and exponents below 1.0 make small movements
+  /// count for more. Non-positive and non-finite exponents are treated as
+  /// linear.
+  Power(f32),
+  /// An exponential curve, `(e^(k*x) - 1) / (e^k - 1)`. Positive `k` is
+  /// gentle at first and steep near the end, negative `k` is the reverse. A
+  /// `k` of 0.0 is linear, and so is a non-finite `k`. Anything past 80.0
+  /// either way is clamped to that, since `e^k` overflows not far beyond it.
+  Exponential(f32),
+  /// An S shaped curve, `x^k / (x^k + (1-x)^k)`. With `k` above 1.0 the
+  /// curve is flat at both ends and steep in the middle, and below 1.0 it's
+  /// the other way around. A `k` of 1.0 is linear. Non-positive and
+  /// non-finite `k` values are treated as linear, and `k` is clamped to at
+  /// most 100.0 so that the two powers don't both round down to 0.0.
+  SCurve(f32),
+  /// A cubic Bézier curve from (0, 0) to (1, 1), with the two control points
+  /// given, the same as a CSS `cubic-bezier` timing function. The X values of
+  /// the control points are clamped to the range 0.0 to 1.0 so that the curve
+  /// is always a function of the input.
+  CubicBezier {
+    /// X of the first control point.
+    x1: f32,
+    /// Y of the first control point.
+    y1: f32,
+    /// X of the second control point.
+    x2: f32,
+    /// Y of the second control point.
+    y2: f32,
+  },
+  /// Points of `(input, output)`, with straight lines between them when
+  /// they're sorted by input. The points don't have to be in order. Inputs
+  /// before the first point or after the last one get the output of that end
+  /// point. An empty table is linear.
+  Lookup(Vec<(f32, f32)>),
+}
+
+impl Default for ResponseCurve {
+  /// `Linear`
+  fn default() -> Self {
+    ResponseCurve::Linear
+  }
+}
+
+impl ResponseCurve {
+  /// Makes a lookup table curve, sorting the points by input.
+  pub fn lookup(mut points: Vec<(f32, f32)>) -> Self {
+    points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(::std::cmp::Ordering::Equal));
+    ResponseCurve::Lookup(points)
+  }
+
+  /// Applies the curve to a magnitude in the range 0.0 to 1.0.
+  pub fn apply(&self, input: f32) -> f32 {
+    // this also turns NaN into 0.0
+    let x = if input > 0.0 { input.min(1.0) } else { 0.0 };
+    let y = match *self {
+      ResponseCurve::Linear => x,
+      ResponseCurve::Power(exponent) => {
+        if exponent > 0.0 && exponent.is_finite() {
+          x.powf(exponent)
+        } else {
+          x
+        }
+      }
+      ResponseCurve::Exponential(k) => {
+        if k.abs() < 1e-6 || !k.is_finite() {
+          x
+        } else {
+          let k = k.clamp(-80.0, 80.0);
+          (k * x).exp_m1() / k.exp_m1()
+        }
+      }
+      ResponseCurve::SCurve(k) => {
+        if k > 0.0 && k.is_finite() {
+          let k = k.min(100.0);
+          let a = x.powf(k);
+          let b = (1.0 - x).powf(k);
+          a / (a + b)
+        } else {
+          x
+        }
+      }
+      ResponseCurve::CubicBezier { x1, y1, x2, y2 } => {
+        cubic_bezier(x, x1.clamp(0.0, 1.0), y1, x2.clamp(0.0, 1.0), y2)
+      }
+      ResponseCurve::Lookup(ref points) => lookup(points, x),
+    };
+    if y > 0.0 {
+      y.min(1.0)
+    } else {
+      0.0
+    }
+  }
+
+  /// Applies the curve to a value in the range -1.0 to 1.0, keeping the sign.
+  pub fn apply_signed(&self, input: f32) -> f32 {
+    self.apply(input.abs()).copysign(input)
+  }
+
+  /// Applies the curve to the length of a normalized stick value, keeping the
+  /// direction.
+  ///
+  /// Use this on the output of a deadzone. Curving each axis on its own would
+  /// bend diagonal movement towards the axes.
+  pub fn apply_stick(&self, stick: (f32, f32)) -> (f32, f32) {
+    let length = (stick.0 * stick.0 + stick.1 * stick.1).sqrt();
+    if length > 0.0 {
+      let scale = self.apply(length) / length;
+      (stick.0 * scale, stick.1 * scale)
+    } else {
+      (0.0, 0.0)
+    }
+  }
+
+  /// Applies the curve to a raw trigger value, giving a value from 0.0 to 1.0.
+  pub fn apply_trigger(&self, raw_trigger: u8) -> f32 {
+    self.apply(f32::from(raw_trigger) / 255.0)
+  }
+}
+
+/// This doesn't need the points to be sorted, since a table that came from a
+/// settings file might not be.
+fn lookup(points: &[(f32, f32)], x: f32) -> f32 {
+  // The closest point before `x`, and the closest point at or after it.
+  let mut below: Option<(f32, f32)> = None;
+  let mut above: Option<(f32, f32)> = None;
+  for &p in points {
+    if p.0 < x {
+      let closer = match below {
+        Some(b) => p.0 >= b.0,
+        None => true,
+      };
+      if closer {
+        below = Some(p);
+      }
+    } else if p.0 >= x {
+      let closer = match above {
+        Some(a) => p.0 < a.0,
+        None => true,
+      };
+      if closer {
+        above = Some(p);
+      }
+    }
+  }
+  match (below, above) {
+    (Some((x0, y0)), Some((x1, y1))) => y0 + (y1 - y0) * (x - x0) / (x1 - x0),
+    (Some(b), None) => b.1,
+    (None, Some(a)) => a.1,
+    (None, None) => x,
+  }
+}
+
+fn cubic_bezier(x: f32, x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
+  // One coordinate of the curve at `t`, given the two control values.
+  let at = |t: f32, c1: f32, c2: f32| {
+    let u = 1.0 - t;
+    3.0 * u * u * t * c1 + 3.0 * u * t * t * c2 + t * t * t
+  };
+  // With the control X values in 0..=1 the X coordinate only ever goes up as
+  // `t` goes up, so a bisection always finds the right `t`.
+  let mut low = 0.0;
+  let mut high = 1.0;
+  for _ in 0..32 {
+    let mid = (low + high) * 0.5;
+    if at(mid, x1, x2) < x {
+      low = mid;
+    } else {
+      high = mid;
+    }
+  }
+  at((low + high) * 0.5, y1, y2)
+}
+
+#[test]
+fn response_curve_test() {
+  let close = |a: f32, b: f32| (a - b).abs() < 1e-3;
+  let curves = [
+    ResponseCurve::Linear,
+    ResponseCurve::Power(2.0),
+    ResponseCurve::Exponential(3.0),
+    ResponseCurve::Exponential(-3.0),
+    ResponseCurve::SCurve(3.0),
+    ResponseCurve::CubicBezier {
+      x1: 0.42,
+      y1: 0.0,
+      x2: 0.58,
+      y2: 1.0,
+    },
+    ResponseCurve::lookup(vec![(1.0, 1.0), (0.0, 0.0), (0.5, 0.2)]),
+    // extreme and non-finite parameters
+    ResponseCurve::Power(f32::INFINITY),
+    ResponseCurve::Exponential(1000.0),
+    ResponseCurve::Exponential(-1000.0),
+    ResponseCurve::Exponential(f32::NAN),
+    ResponseCurve::SCurve(1e6),
+    ResponseCurve::SCurve(f32::NAN),
+  ];
+  for curve in curves.iter() {
+    assert!(close(curve.apply(0.0), 0.0), "{:?}", curve);
+    assert!(close(curve.apply(1.0), 1.0), "{:?}", curve);
+    assert!(close(curve.apply(7.0), 1.0), "{:?}", curve);
+    assert!(close(curve.apply(f32::NAN), 0.0), "{:?}", curve);
+    // all of these curves are monotonic
+    let mut last = 0.0;
+    for i in 0..=100 {
+      let y = curve.apply(i as f32 / 100.0);
+      assert!(y >= last - 1e-6, "{:?}", curve);
+      last = y;
+    }
+  }
+
+  assert!(close(ResponseCurve::Power(2.0).apply(0.5), 0.25));
+  assert!(close(ResponseCurve::SCurve(2.0).apply(0.5), 0.5));
+  assert!(ResponseCurve::SCurve(2.0).apply(0.25) < 0.25);
+  assert!(ResponseCurve::Exponential(3.0).apply(0.5) < 0.5);
+  assert!(ResponseCurve::Exponential(-3.0).apply(0.5) > 0.5);
+  assert!(close(curves[6].apply(0.75), 0.6));
+  assert!(close(ResponseCurve::Power(f32::NAN).apply(0.3), 0.3));
+  assert!(close(
+    ResponseCurve::Exponential(f32::INFINITY).apply(0.3),
+    0.3
+  ));
+  assert!(close(ResponseCurve::Exponential(f32::NAN).apply(0.3), 0.3));
+  assert!(close(ResponseCurve::SCurve(f32::INFINITY).apply(0.3), 0.3));
+  assert!(close(ResponseCurve::SCurve(1e6).apply(0.5), 0.5));
+  let linear_bezier = ResponseCurve::CubicBezier {
+    x1: 0.25,
+    y1: 0.25,
+    x2: 0.75,
+    y2: 0.75,
+  };
+  assert!(close(linear_bezier.apply(0.3), 0.3));
+
+  assert!(close(ResponseCurve::Power(2.0).apply_signed(-0.5), -0.25));
+  let stick = ResponseCurve::Power(2.0).apply_stick((0.0, -0.5));
+  assert!(close(stick.0, 0.0) && close(stick.1, -0.25));
+  assert!(close(ResponseCurve::Linear.apply_trigger(255), 1.0));
+
+  // a table that skipped `lookup` still works
+  let unsorted = ResponseCurve::Lookup(vec![(1.0, 1.0), (0.0, 0.0), (0.5, 0.2)]);
+  assert!(close(unsorted.apply(0.75), 0.6));
+  assert!(close(unsorted.apply(0.25), 0.1));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn response_curve_serde_test() {
+  extern crate serde_json;
+
+  let curve: ResponseCurve = serde_json::from_str(r#"{"Lookup":[[1,1],[0,0],[0.5,0.2]]}"#).unwrap();
+  assert!((curve.apply(0.75) - 0.6).abs() < 1e-3);
+  let json = serde_json::to_string(&curve).unwrap();
+  let again: ResponseCurve = serde_json::from_str(&json).unwrap();
+  assert_eq!(again, curve);
+  assert!((again.apply(0.75) - 0.6).abs() < 1e-3);
+}
@@ -0,0 +1,166 @@
+//! Comparing one controller state with the next.
+
+use super::{Button, Buttons, XInputState};
+
+/// The change in a single digital input between two states.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum Edge {
+  /// Up in both states.
+  Released,
+  /// Up before, down now.
+  JustPressed,
+  /// Down in both states.
+  Held,
+  /// Down before, up now.
+  JustReleased,
+}
+
+impl Edge {
+  /// The edge from the input being `was_down` to it being `is_down`.
+  #[inline]
+  pub fn new(was_down: bool, is_down: bool) -> Self {
+    match (was_down, is_down) {
+      (false, false) => Edge::Released,
+      (false, true) => Edge::JustPressed,
+      (true, true) => Edge::Held,
+      (true, false) => Edge::JustReleased,
+    }
+  }
+
+  /// If the input is down in the current state.
+  #[inline]
+  pub fn is_down(self) -> bool {
+    self == Edge::JustPressed || self == Edge::Held
+  }
+
+  /// If the input changed between the two states.
+  #[inline]
+  pub fn changed(self) -> bool {
+    self == Edge::JustPressed || self == Edge::JustReleased
+  }
+}
+
+/// What changed from one `XInputState` to the next.
+///
+/// Since this compares whole states, it doesn't matter how many packets went
+/// by in between: it's always the difference between the two states you give
+/// it. The flip side is that a button that was pressed _and_ released between
+/// two polls won't show up at all, so poll at least once a frame.
+///
+/// Triggers count as down when they're past `XInputState::TRIGGER_THRESHOLD`,
+/// same as `left_trigger_bool` and `right_trigger_bool`. Sticks are compared
+/// after the default deadzone is applied, so jitter inside the deadzone isn't
+/// movement.
+#[derive(Debug, Copy, Clone)]
+pub struct StateDelta {
+  /// The buttons that are down now but weren't before.
+  pub just_pressed: Buttons,
+  /// The buttons that were down before but aren't now.
+  pub just_released: Buttons,
+  /// The buttons that are down in both states.
+  pub held: Buttons,
+  /// The left trigger, as a digital input.
+  pub left_trigger: Edge,
+  /// The right trigger, as a digital input.
+  pub right_trigger: Edge,
+  /// How far the left stick moved, as `current - previous` of the normalized
+  /// stick values.
+  pub left_stick_motion: (f32, f32),
+  /// How far the right stick moved, as `current - previous` of the normalized
+  /// stick values.
+  pub right_stick_motion: (f32, f32),
+  /// If the packet numbers of the two states are different. When this is
+  /// `false` nothing at all changed.
+  pub packet_changed: bool,
+}
+
+impl StateDelta {
+  /// Computes the changes from `previous` to `current`.
+  pub fn new(previous: &XInputState, current: &XInputState) -> Self {
+    let before = previous.buttons();
+    let now = current.buttons();
+    let motion = |before: (f32, f32), now: (f32, f32)| (now.0 - before.0, now.1 - before.1);
+    StateDelta {
+      just_pressed: now - before,
+      just_released: before - now,
+      held: now & before,
+      left_trigger: Edge::new(previous.left_trigger_bool(), current.left_trigger_bool()),
+      right_trigger: Edge::new(previous.right_trigger_bool(), current.right_trigger_bool()),
+      left_stick_motion: motion(
+        previous.left_stick_normalized(),
+        current.left_stick_normalized(),
+      ),
+      right_stick_motion: motion(
+        previous.right_stick_normalized(),
+        current.right_stick_normalized(),
+      ),
+      packet_changed: previous.raw.dwPacketNumber != current.raw.dwPacketNumber,
+    }
+  }
+
+  /// The edge for one button.
+  #[inline]
+  pub fn button(&self, button: Button) -> Edge {
+    let is_down = self.just_pressed.contains(button) || self.held.contains(button);
+    let was_down = self.just_released.contains(button) || self.held.contains(button);
+    Edge::new(was_down, is_down)
+  }
+
+  /// If the left stick moved more than `epsilon` (in normalized units).
+  #[inline]
+  pub fn left_stick_moved(&self, epsilon: f32) -> bool {
+    let (x, y) = self.left_stick_motion;
+    (x * x + y * y).sqrt() > epsilon
+  }
+
+  /// If the right stick moved more than `epsilon` (in normalized units).
+  #[inline]
+  pub fn right_stick_moved(&self, epsilon: f32) -> bool {
+    let (x, y) = self.right_stick_motion;
+    (x * x + y * y).sqrt() > epsilon
+  }
+
+  /// If any button or trigger was pressed or released.
+  #[inline]
+  pub fn any_digital_change(&self) -> bool {
+    !self.just_pressed.is_empty()
+      || !self.just_released.is_empty()
+      || self.left_trigger.changed()
+      || self.right_trigger.changed()
+  }
+}
+
+#[test]
+fn state_delta_test() {
+  use super::{XINPUT_GAMEPAD_A, XINPUT_GAMEPAD_B, XINPUT_GAMEPAD_X};
+
+  let mut before = XInputState::default();
+  before.raw.dwPacketNumber = 1;
+  before.raw.Gamepad.wButtons = XINPUT_GAMEPAD_A | XINPUT_GAMEPAD_B;
+  before.raw.Gamepad.bRightTrigger = 255;
+  before.raw.Gamepad.sThumbLX = 1000;
+  let mut after = before;
+  after.raw.dwPacketNumber = 7;
+  after.raw.Gamepad.wButtons = XINPUT_GAMEPAD_B | XINPUT_GAMEPAD_X;
+  after.raw.Gamepad.bLeftTrigger = 255;
+  after.raw.Gamepad.bRightTrigger = 0;
+  after.raw.Gamepad.sThumbLX = 2000;
+  after.raw.Gamepad.sThumbRY = i16::MAX;
+
+  let delta = StateDelta::new(&before, &after);
+  assert!(delta.packet_changed && delta.any_digital_change());
+  assert_eq!(delta.just_pressed, Buttons::from(Button::West));
+  assert_eq!(delta.just_released, Buttons::from(Button::South));
+  assert_eq!(delta.held, Buttons::from(Button::East));
+  assert_eq!(delta.button(Button::South), Edge::JustReleased);
+  assert_eq!(delta.button(Button::North), Edge::Released);
+  assert_eq!(delta.left_trigger, Edge::JustPressed);
+  assert_eq!(delta.right_trigger, Edge::JustReleased);
+  // both left stick values are inside the deadzone
+  assert!(!delta.left_stick_moved(0.0));
+  assert!(delta.right_stick_moved(0.9));
+
+  let same = StateDelta::new(&after, &after);
+  assert!(!same.packet_changed && !same.any_digital_change());
+  assert_eq!(same.held, after.buttons());
+}
@@ -0,0 +1,409 @@
+//! Reading a stick (or the D-pad) as a digital direction.
+
+/// A direction limited to the four cardinal directions.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum Direction4 {
+  /// No direction.
+  Neutral,
+  /// Up
+  Up,
+  /// Down
+  Down,
+  /// Left
+  Left,
+  /// Right
+  Right,
+}
+
+/// A direction that can also be a diagonal.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum Direction8 {
+  /// No direction.
+  Neutral,
+  /// Up
+  Up,
+  /// Up and to the right.
+  UpRight,
+  /// Right
+  Right,
+  /// Down and to the right.
+  DownRight,
+  /// Down
+  Down,
+  /// Down and to the left.
+  DownLeft,
+  /// Left
+  Left,
+  /// Up and to the left.
+  UpLeft,
+}
+
+impl Direction8 {
+  /// The eight non-neutral directions, going clockwise from `Up`.
+  pub const ALL: [Direction8; 8] = [
+    Direction8::Up,
+    Direction8::UpRight,
+    Direction8::Right,
+    Direction8::DownRight,
+    Direction8::Down,
+    Direction8::DownLeft,
+    Direction8::Left,
+    Direction8::UpLeft,
+  ];
+
+  /// The direction with the signs of `x` and `y`. Positive values are to the
+  /// right (X-axis) or up (Y-axis).
+  pub fn from_vector(x: i32, y: i32) -> Self {
+    match (x.signum(), y.signum()) {
+      (0, 1) => Direction8::Up,
+      (1, 1) => Direction8::UpRight,
+      (1, 0) => Direction8::Right,
+      (1, -1) => Direction8::DownRight,
+      (0, -1) => Direction8::Down,
+      (-1, -1) => Direction8::DownLeft,
+      (-1, 0) => Direction8::Left,
+      (-1, 1) => Direction8::UpLeft,
+      _ => Direction8::Neutral,
+    }
+  }
+
+  /// The direction as `(x, y)`, with each part being -1, 0, or 1.
+  pub fn vector(self) -> (i32, i32) {
+    match self {
+      Direction8::Neutral => (0, 0),
+      Direction8::Up => (0, 1),
+      Direction8::UpRight => (1, 1),
+      Direction8::Right => (1, 0),
+      Direction8::DownRight => (1, -1),
+      Direction8::Down => (0, -1),
+      Direction8::DownLeft => (-1, -1),
+      Direction8::Left => (-1, 0),
+      Direction8::UpLeft => (-1, 1),
+    }
+  }
+
+  /// The direction of four D-pad buttons. Pressing two opposite directions at
+  /// once cancels both of them out.
+  pub fn from_dpad(up: bool, down: bool, left: bool, right: bool) -> Self {
+    Direction8::from_vector(
+      i32::from(right) - i32::from(left),
+      i32::from(up) - i32::from(down),
+    )
+  }
+
+  /// If this is one of the four diagonals.
+  pub fn is_diagonal(self) -> bool {
+    let (x, y) = self.vector();
+    x != 0 && y != 0
+  }
+
+  /// The direction in fighting game "numpad notation", where each direction is
+  /// the digit in that spot on a number pad: 8 is up, 6 is right, 2 is down, 4
+  /// is left, the diagonals are the corners, and 5 is neutral.
+  pub fn numpad(self) -> u8 {
+    let (x, y) = self.vector();
+    (5 + x + 3 * y) as u8
+  }
+
+  /// The direction for a numpad notation digit, if it's from 1 to 9.
+  pub fn from_numpad(digit: u8) -> Option<Self> {
+    if (1..=9).contains(&digit) {
+      let i = i32::from(digit) - 1;
+      Some(Direction8::from_vector(i % 3 - 1, i / 3 - 1))
+    } else {
+      None
+    }
+  }
+
+  /// The direction flipped left to right.
+  pub fn mirrored(self) -> Self {
+    let (x, y) = self.vector();
+    Direction8::from_vector(-x, y)
+  }
+
+  /// The angle of this direction in degrees, counter-clockwise from `Right`.
+  /// `Neutral` doesn't have an angle.
+  fn angle(self) -> Option<f32> {
+    match self {
+      Direction8::Neutral => None,
+      Direction8::Up => Some(90.0),
+      Direction8::UpRight => Some(45.0),
+      Direction8::Right => Some(0.0),
+      Direction8::DownRight => Some(-45.0),
+      Direction8::Down => Some(-90.0),
+      Direction8::DownLeft => Some(-135.0),
+      Direction8::Left => Some(180.0),
+      Direction8::UpLeft => Some(135.0),
+    }
+  }
+}
+
+impl From<Direction4> for Direction8 {
+  fn from(d: Direction4) -> Self {
+    match d {
+      Direction4::Neutral => Direction8::Neutral,
+      Direction4::Up => Direction8::Up,
+      Direction4::Down => Direction8::Down,
+      Direction4::Left => Direction8::Left,
+      Direction4::Right => Direction8::Right,
+    }
+  }
+}
+
+impl Direction4 {
+  /// The same direction if it isn't a diagonal.
+  pub fn from_direction8(d: Direction8) -> Option<Self> {
+    match d {
+      Direction8::Neutral => Some(Direction4::Neutral),
+      Direction8::Up => Some(Direction4::Up),
+      Direction8::Down => Some(Direction4::Down),
+      Direction8::Left => Some(Direction4::Left),
+      Direction8::Right => Some(Direction4::Right),
+      _ => None,
+    }
+  }
+}
+
+/// The smallest angle between two angles, in degrees.
+pub(crate) fn angle_between(a: f32, b: f32) -> f32 {
+  let d = (a - b).rem_euclid(360.0);
+  d.min(360.0 - d)
+}
+
+/// Turns a normalized stick value into a `Direction4` or `Direction8`.
+///
+/// The stick has to be pushed past the `press` magnitude to leave neutral,
+/// and then drop below the `release` magnitude to go back to it. Which
+/// direction you get depends on the stick's angle:
+///
+/// * With `update4` each direction gets a 90 degree sector.
+/// * With `update8` each diagonal gets a sector of `diagonal_width` degrees,
+///   and each cardinal direction gets whatever is left over. Narrow diagonals
+///   make it easier to hold a cardinal direction, which is often what you want
+///   in menus.
+///
+/// Once a direction is chosen, its sector is widened by `hysteresis` degrees
+/// on each side, so a stick that's sitting right on a boundary doesn't flicker
+/// between the two directions.
+///
+/// Since the quantizer remembers the last direction, you need one per stick,
+/// and you should stick to either `update4` or `update8` with each one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionQuantizer {
+  press: f32,
+  release: f32,
+  diagonal_width: f32,
+  hysteresis: f32,
+  current: Direction8,
+}
+
+impl Default for DirectionQuantizer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl DirectionQuantizer {
+  /// A quantizer with the default settings.
+  ///
+  /// * `press`: 0.5
+  /// * `release`: 0.4
+  /// * `diagonal_width`: 45 degrees
+  /// * `hysteresis`: 5 degrees
+  pub fn new() -> Self {
+    DirectionQuantizer {
+      press: 0.5,
+      release: 0.4,
+      diagonal_width: 45.0,
+      hysteresis: 5.0,
+      current: Direction8::Neutral,
+    }
+  }
+
+  /// Sets the magnitudes to leave and return to neutral. `release` is lowered
+  /// to `press` if it's above it.
+  pub fn with_activation(mut self, press: f32, release: f32) -> Self {
+    self.press = press;
+    self.release = release.min(press);
+    self
+  }
+
+  /// Sets the size of the diagonal sectors for `update8`, from 0 to 90
+  /// degrees. A non-finite width gives the default of 45 degrees.
+  pub fn with_diagonal_width(mut self, degrees: f32) -> Self {
+    let degrees = if degrees.is_finite() { degrees } else { 45.0 };
+    self.diagonal_width = degrees.clamp(0.0, 90.0);
+    self
+  }
+
+  /// Sets how many degrees the current direction's sector is widened by on
+  /// each side.
+  pub fn with_hysteresis(mut self, degrees: f32) -> Self {
+    self.hysteresis = degrees.max(0.0);
+    self
+  }
+
+  /// The most recent direction.
+  pub fn current(&self) -> Direction8 {
+    self.current
+  }
+
+  /// Goes back to neutral, eg: when the controller is disconnected.
+  pub fn reset(&mut self) {
+    self.current = Direction8::Neutral;
+  }
+
+  fn half_width(&self, d: Direction8, eight_way: bool) -> f32 {
+    if !eight_way {
+      45.0
+    } else if d.is_diagonal() {
+      self.diagonal_width / 2.0
+    } else {
+      (90.0 - self.diagonal_width) / 2.0
+    }
+  }
+
+  fn quantize(&mut self, stick: (f32, f32), eight_way: bool) -> Direction8 {
+    let magnitude = (stick.0 * stick.0 + stick.1 * stick.1).sqrt();
+    let needed = if self.current == Direction8::Neutral {
+      self.press
+    } else {
+      self.release
+    };
+    if !(magnitude >= needed && magnitude > 0.0) {
+      self.current = Direction8::Neutral;
+      return self.current;
+    }
+    let angle = stick.1.atan2(stick.0).to_degrees();
+    if let Some(current_angle) = self.current.angle() {
+      let still_fits = eight_way || !self.current.is_diagonal();
+      let reach = self.half_width(self.current, eight_way) + self.hysteresis;
+      if still_fits && angle_between(angle, current_angle) <= reach {
+        return self.current;
+      }
+    }
+    // The direction that the angle is the furthest inside the sector of.
+    let mut best = (Direction8::Neutral, f32::INFINITY);
+    for &d in Direction8::ALL.iter() {
+      if eight_way || !d.is_diagonal() {
+        let outside = angle_between(angle, d.angle().unwrap()) - self.half_width(d, eight_way);
+        if outside < best.1 {
+          best = (d, outside);
+        }
+      }
+    }
+    self.current = best.0;
+    self.current
+  }
+
+  /// Feeds in a new stick value, giving a four way direction.
+  pub fn update4(&mut self, stick: (f32, f32)) -> Direction4 {
+    let d = self.quantize(stick, false);
+    Direction4::from_direction8(d).unwrap_or(Direction4::Neutral)
+  }
+
+  /// Feeds in a new stick value, giving an eight way direction.
+  pub fn update8(&mut self, stick: (f32, f32)) -> Direction8 {
+    self.quantize(stick, true)
+  }
+
+  /// Like `update4`, but a D-pad direction takes priority over the stick.
+  ///
+  /// A diagonal on the D-pad keeps the current direction if it's one half of
+  /// that diagonal, otherwise the horizontal half is used.
+  pub fn update4_with_dpad(&mut self, stick: (f32, f32), dpad: Direction8) -> Direction4 {
+    if dpad == Direction8::Neutral {
+      return self.update4(stick);
+    }
+    let (x, y) = dpad.vector();
+    let (cx, cy) = self.current.vector();
+    let keep_current = dpad.is_diagonal() && ((cx == x && cy == 0) || (cy == y && cx == 0));
+    if !keep_current {
+      self.current = if x != 0 {
+        Direction8::from_vector(x, 0)
+      } else {
+        Direction8::from_vector(0, y)
+      };
+    }
+    Direction4::from_direction8(self.current).unwrap_or(Direction4::Neutral)
+  }
+
+  /// Like `update8`, but a D-pad direction takes priority over the stick.
+  pub fn update8_with_dpad(&mut self, stick: (f32, f32), dpad: Direction8) -> Direction8 {
+    if dpad == Direction8::Neutral {
+      self.update8(stick)
+    } else {
+      self.current = dpad;
+      dpad
+    }
+  }
+}
+
+#[test]
+fn direction_quantizer_test() {
+  let polar = |degrees: f32, length: f32| {
+    let r = degrees.to_radians();
+    (r.cos() * length, r.sin() * length)
+  };
+
+  let mut q = DirectionQuantizer::new();
+  assert_eq!(q.update8(polar(90.0, 0.45)), Direction8::Neutral);
+  assert_eq!(q.update8(polar(90.0, 0.6)), Direction8::Up);
+  // released by magnitude hysteresis only once below 0.4
+  assert_eq!(q.update8(polar(90.0, 0.45)), Direction8::Up);
+  assert_eq!(q.update8(polar(90.0, 0.3)), Direction8::Neutral);
+
+  // 45 degree diagonals: the boundary is at 67.5 degrees
+  assert_eq!(q.update8(polar(70.0, 1.0)), Direction8::Up);
+  assert_eq!(q.update8(polar(64.0, 1.0)), Direction8::Up);
+  assert_eq!(q.update8(polar(62.0, 1.0)), Direction8::UpRight);
+  assert_eq!(q.update8(polar(70.0, 1.0)), Direction8::UpRight);
+  assert_eq!(q.update8(polar(-170.0, 1.0)), Direction8::Left);
+  assert_eq!(q.update8(polar(-120.0, 1.0)), Direction8::DownLeft);
+
+  let mut narrow = DirectionQuantizer::new()
+    .with_diagonal_width(20.0)
+    .with_hysteresis(0.0);
+  assert_eq!(narrow.update8(polar(30.0, 1.0)), Direction8::Right);
+  assert_eq!(narrow.update8(polar(40.0, 1.0)), Direction8::UpRight);
+  assert_eq!(
+    DirectionQuantizer::new().with_diagonal_width(f32::NAN),
+    DirectionQuantizer::new()
+  );
+  assert_eq!(
+    DirectionQuantizer::new().with_diagonal_width(f32::INFINITY),
+    DirectionQuantizer::new()
+  );
+
+  let mut four = DirectionQuantizer::new();
+  assert_eq!(four.update4(polar(40.0, 1.0)), Direction4::Right);
+  assert_eq!(four.update4(polar(48.0, 1.0)), Direction4::Right);
+  assert_eq!(four.update4(polar(51.0, 1.0)), Direction4::Up);
+  assert_eq!(four.update4((0.0, 0.0)), Direction4::Neutral);
+
+  // the D-pad wins over the stick
+  let dpad = Direction8::from_dpad(true, false, false, true);
+  assert_eq!(dpad, Direction8::UpRight);
+  assert_eq!(
+    Direction8::from_dpad(true, true, true, false),
+    Direction8::Left
+  );
+  assert_eq!(q.update8_with_dpad(polar(-90.0, 1.0), dpad), dpad);
+  four.update4(polar(90.0, 1.0));
+  assert_eq!(four.update4_with_dpad((0.0, 0.0), dpad), Direction4::Up);
+  four.reset();
+  assert_eq!(four.update4_with_dpad((0.0, 0.0), dpad), Direction4::Right);
+  assert_eq!(
+    four.update4_with_dpad(polar(-90.0, 1.0), Direction8::Neutral),
+    Direction4::Down
+  );
+
+  for &d in Direction8::ALL.iter() {
+    assert_eq!(Direction8::from_numpad(d.numpad()), Some(d));
+  }
+  assert_eq!(Direction8::Neutral.numpad(), 5);
+  assert_eq!(Direction8::DownRight.numpad(), 3);
+  assert_eq!(Direction8::from_numpad(7), Some(Direction8::UpLeft));
+  assert_eq!(Direction8::from_numpad(0), None);
+  assert_eq!(Direction8::DownRight.mirrored(), Direction8::DownLeft);
+}
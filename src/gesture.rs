@@ -0,0 +1,293 @@
+//! Turning button presses over time into taps, long presses, and chords.
+
+use std::time::Duration;
+
+use super::{Button, Buttons, XInputState};
+
+/// Something the player did with the buttons, found by a `GestureDetector`.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum Gesture {
+  /// A button was pressed and released quickly.
+  Tap(Button),
+  /// A button was tapped twice in a row. The first of the two taps is also
+  /// reported as a `Tap` when it happens.
+  DoubleTap(Button),
+  /// A button has been held down for the long press time. This happens while
+  /// the button is still down.
+  LongPress(Button),
+  /// A button that made a `LongPress` was released.
+  HoldReleased {
+    /// The button.
+    button: Button,
+    /// How long it was down for in total.
+    held_for: Duration,
+  },
+  /// All the buttons of a registered chord were pressed close enough together.
+  Chord(Buttons),
+}
+
+const BUTTON_COUNT: usize = Button::ALL.len();
+
+#[derive(Debug, Copy, Clone, Default)]
+struct ButtonTrack {
+  pressed_at: Option<Duration>,
+  last_tap_release: Option<Duration>,
+  second_press: bool,
+  long_press_sent: bool,
+  in_chord: bool,
+}
+
+/// Watches a controller's buttons over time and reports `Gesture`s.
+///
+/// Feed it each new state along with a timestamp using `update`. The
+/// timestamps can start from whenever you like (game time, time since launch,
+/// etc), they just need to never go backwards. Gestures are found from the
+/// states you give it, so poll at least as often as your shortest window.
+///
+/// Each gesture has its own timing:
+///
+/// * `tap_time`: The longest a press can be and still be a `Tap`.
+/// * `double_tap_window`: The longest time from the release of a tap until
+///   the next press, for the pair to be a `DoubleTap`.
+/// * `long_press_time`: How long a button has to be held for a `LongPress`.
+/// * `chord_window`: The longest time between the first and last press of a
+///   chord's buttons.
+///
+/// Once a chord happens, the buttons of that chord don't make any other
+/// gestures until they're released and pressed again. That way pressing a
+/// chord doesn't also tap each of its buttons.
+#[derive(Debug, Clone)]
+pub struct GestureDetector {
+  tap_time: Duration,
+  double_tap_window: Duration,
+  long_press_time: Duration,
+  chord_window: Duration,
+  chords: Vec<Buttons>,
+  buttons: Buttons,
+  tracks: [ButtonTrack; BUTTON_COUNT],
+  events: Vec<Gesture>,
+}
+
+impl Default for GestureDetector {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl GestureDetector {
+  /// A detector with the default timings and no chords.
+  ///
+  /// * `tap_time`: 200ms
+  /// * `double_tap_window`: 250ms
+  /// * `long_press_time`: 500ms
+  /// * `chord_window`: 50ms
+  pub fn new() -> Self {
+    GestureDetector {
+      tap_time: Duration::from_millis(200),
+      double_tap_window: Duration::from_millis(250),
+      long_press_time: Duration::from_millis(500),
+      chord_window: Duration::from_millis(50),
+      chords: Vec::new(),
+      buttons: Buttons::NONE,
+      tracks: [ButtonTrack::default(); BUTTON_COUNT],
+      events: Vec::new(),
+    }
+  }
+
+  /// Sets the longest a press can be and still be a tap.
+  pub fn with_tap_time(mut self, time: Duration) -> Self {
+    self.tap_time = time;
+    self
+  }
+
+  /// Sets the longest time between two taps for a double tap.
+  pub fn with_double_tap_window(mut self, window: Duration) -> Self {
+    self.double_tap_window = window;
+    self
+  }
+
+  /// Sets how long a button has to be held for a long press.
+  pub fn with_long_press_time(mut self, time: Duration) -> Self {
+    self.long_press_time = time;
+    self
+  }
+
+  /// Sets the longest time between the first and last press of a chord.
+  pub fn with_chord_window(mut self, window: Duration) -> Self {
+    self.chord_window = window;
+    self
+  }
+
+  /// Adds a chord to watch for. Chords of fewer than two buttons are ignored.
+  pub fn with_chord(mut self, chord: Buttons) -> Self {
+    self.add_chord(chord);
+    self
+  }
+
+  /// Adds a chord to watch for. Chords of fewer than two buttons are ignored.
+  pub fn add_chord(&mut self, chord: Buttons) {
+    let chord = chord & Buttons::ALL;
+    if chord.len() >= 2 && !self.chords.contains(&chord) {
+      self.chords.push(chord);
+    }
+  }
+
+  /// The chords being watched for.
+  pub fn chords(&self) -> &[Buttons] {
+    &self.chords
+  }
+
+  /// Forgets all buttons and timings, eg: when the controller is
+  /// disconnected. The settings and chords are kept.
+  pub fn reset(&mut self) {
+    self.buttons = Buttons::NONE;
+    self.tracks = [ButtonTrack::default(); BUTTON_COUNT];
+    self.events.clear();
+  }
+
+  /// Feeds in a new state, giving back the gestures that it completed.
+  pub fn update(&mut self, state: &XInputState, now: Duration) -> &[Gesture] {
+    self.events.clear();
+    let current = state.buttons();
+    let pressed = current - self.buttons;
+    let released = self.buttons - current;
+    self.buttons = current;
+
+    for button in released {
+      let track = &mut self.tracks[button as usize];
+      let pressed_at = track.pressed_at.take().unwrap_or(now);
+      let held_for = now.checked_sub(pressed_at).unwrap_or_default();
+      if track.in_chord {
+        track.in_chord = false;
+        track.last_tap_release = None;
+      } else if track.long_press_sent {
+        self.events.push(Gesture::HoldReleased { button, held_for });
+        track.last_tap_release = None;
+      } else if held_for <= self.tap_time {
+        if track.second_press {
+          self.events.push(Gesture::DoubleTap(button));
+          track.last_tap_release = None;
+        } else {
+          self.events.push(Gesture::Tap(button));
+          track.last_tap_release = Some(now);
+        }
+      } else {
+        track.last_tap_release = None;
+      }
+      track.second_press = false;
+      track.long_press_sent = false;
+    }
+
+    for button in pressed {
+      let double_tap_window = self.double_tap_window;
+      let track = &mut self.tracks[button as usize];
+      track.pressed_at = Some(now);
+      track.second_press = track
+        .last_tap_release
+        .and_then(|released_at| now.checked_sub(released_at))
+        .is_some_and(|gap| gap <= double_tap_window);
+    }
+
+    if !pressed.is_empty() {
+      for &chord in self.chords.iter() {
+        if !chord.intersects(pressed) || !current.contains_all(chord) {
+          continue;
+        }
+        let tracks = &mut self.tracks;
+        if chord.iter().any(|b| tracks[b as usize].in_chord) {
+          continue;
+        }
+        let times = chord.iter().filter_map(|b| tracks[b as usize].pressed_at);
+        let first = times.clone().min().unwrap_or(now);
+        let last = times.max().unwrap_or(now);
+        if last - first <= self.chord_window {
+          self.events.push(Gesture::Chord(chord));
+          for b in chord {
+            tracks[b as usize].in_chord = true;
+          }
+        }
+      }
+    }
+
+    for button in current {
+      let track = &mut self.tracks[button as usize];
+      if let Some(pressed_at) = track.pressed_at {
+        let held_for = now.checked_sub(pressed_at).unwrap_or_default();
+        if !track.in_chord && !track.long_press_sent && held_for >= self.long_press_time {
+          track.long_press_sent = true;
+          self.events.push(Gesture::LongPress(button));
+        }
+      }
+    }
+
+    &self.events
+  }
+}
+
+#[test]
+fn gesture_detector_test() {
+  use super::{XINPUT_GAMEPAD_A, XINPUT_GAMEPAD_B, XINPUT_GAMEPAD_LEFT_SHOULDER};
+
+  let state = |w_buttons: u16| {
+    let mut state = XInputState::default();
+    state.raw.Gamepad.wButtons = w_buttons;
+    state
+  };
+  let ms = Duration::from_millis;
+  let chord = Button::LeftShoulder | Button::East;
+  let mut detector = GestureDetector::new().with_chord(chord);
+  let mut run = |steps: &[(u64, u16)]| -> Vec<Gesture> {
+    let mut all = Vec::new();
+    for &(at, w_buttons) in steps {
+      all.extend_from_slice(detector.update(&state(w_buttons), ms(at)));
+    }
+    all
+  };
+  let a = XINPUT_GAMEPAD_A;
+
+  // tap, then a second tap soon after
+  assert_eq!(
+    run(&[(0, a), (100, 0), (200, a), (300, 0)]),
+    vec![Gesture::Tap(Button::South), Gesture::DoubleTap(Button::South)]
+  );
+  // a third tap starts over
+  assert_eq!(
+    run(&[(400, a), (450, 0)]),
+    vec![Gesture::Tap(Button::South)]
+  );
+  // too slow for a double tap
+  assert_eq!(
+    run(&[(1000, a), (1050, 0)]),
+    vec![Gesture::Tap(Button::South)]
+  );
+  // a long press, then its release
+  assert_eq!(
+    run(&[(2000, a), (2400, a), (2500, a), (2600, a), (2900, 0)]),
+    vec![
+      Gesture::LongPress(Button::South),
+      Gesture::HoldReleased {
+        button: Button::South,
+        held_for: ms(900),
+      },
+    ]
+  );
+  // held too long for a tap but not long enough for a long press
+  assert!(run(&[(3000, a), (3300, 0)]).is_empty());
+
+  // a chord within the window, which doesn't also tap or long press
+  let lb = XINPUT_GAMEPAD_LEFT_SHOULDER;
+  let b = XINPUT_GAMEPAD_B;
+  assert_eq!(
+    run(&[(4000, lb), (4030, lb | b), (5000, lb | b), (5050, 0)]),
+    vec![Gesture::Chord(chord)]
+  );
+  // a chord pressed too slowly is just two presses
+  assert_eq!(
+    run(&[(6000, lb), (6100, lb | b), (6150, lb), (6160, 0)]),
+    vec![Gesture::Tap(Button::East), Gesture::Tap(Button::LeftShoulder)]
+  );
+
+  detector.reset();
+  assert!(detector.update(&state(0), ms(7000)).is_empty());
+  assert_eq!(detector.chords(), &[chord]);
+}
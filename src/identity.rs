@@ -0,0 +1,170 @@
+//! Working out which physical controller is in a slot.
+
+use std::fmt::{self, Display, Formatter};
+
+use super::XINPUT_CAPABILITIES_EX;
+
+/// Something about a controller model that you might need to work around.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum Quirk {
+  /// The controller has no rumble motors, so `set_state` does nothing you can
+  /// feel.
+  NoRumble,
+  /// The controller has rumble motors in the triggers, but XInput has no way
+  /// to drive them.
+  ImpulseTriggersUnavailable,
+  /// Lots of third party controllers report this same ID, so the name is only
+  /// a best guess.
+  SharedIdentity,
+  /// The ID is for a wireless receiver, and every controller paired with that
+  /// receiver reports the same ID.
+  WirelessReceiver,
+  /// The "controller" is made in software by some other program (eg: Steam
+  /// Input) that is remapping some other device.
+  VirtualDevice,
+}
+
+/// An entry in the table of controller models that this crate knows about.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct KnownController {
+  /// USB Vendor ID
+  pub vendor_id: u16,
+  /// USB Product ID
+  pub product_id: u16,
+  /// Who makes the controller.
+  pub vendor: &'static str,
+  /// The controller's name.
+  pub name: &'static str,
+  /// Any quirks of this model.
+  pub quirks: &'static [Quirk],
+}
+
+macro_rules! known {
+  ($vid:expr, $pid:expr, $vendor:expr, $name:expr, [$($quirk:ident),*]) => {
+    KnownController {
+      vendor_id: $vid,
+      product_id: $pid,
+      vendor: $vendor,
+      name: $name,
+      quirks: &[$(Quirk::$quirk),*],
+    }
+  };
+}
+
+/// Every controller model that `ControllerIdentity::known_controller` can
+/// recognize.
+///
+/// This is far from every XInput controller ever made, it's just the common
+/// ones. Pull requests for more entries are welcome.
+#[rustfmt::skip]
+pub const KNOWN_CONTROLLERS: &[KnownController] = &[
+  known!(0x045E, 0x028E, "Microsoft", "Xbox 360 Controller", [SharedIdentity]),
+  known!(0x045E, 0x0719, "Microsoft", "Xbox 360 Wireless Receiver", [WirelessReceiver]),
+  known!(0x045E, 0x02D1, "Microsoft", "Xbox One Controller", [ImpulseTriggersUnavailable]),
+  known!(0x045E, 0x02DD, "Microsoft", "Xbox One Controller", [ImpulseTriggersUnavailable]),
+  known!(0x045E, 0x02EA, "Microsoft", "Xbox One S Controller", [ImpulseTriggersUnavailable]),
+  known!(0x045E, 0x02E3, "Microsoft", "Xbox One Elite Controller", [ImpulseTriggersUnavailable]),
+  known!(0x045E, 0x0B00, "Microsoft", "Xbox Elite Series 2 Controller", [ImpulseTriggersUnavailable]),
+  known!(0x045E, 0x0B12, "Microsoft", "Xbox Series X|S Controller", [ImpulseTriggersUnavailable]),
+  known!(0x045E, 0x0B13, "Microsoft", "Xbox Series X|S Controller", [ImpulseTriggersUnavailable]),
+  known!(0x046D, 0xC21D, "Logitech", "Gamepad F310", [NoRumble]),
+  known!(0x046D, 0xC21E, "Logitech", "Gamepad F510", []),
+  known!(0x046D, 0xC21F, "Logitech", "Wireless Gamepad F710", []),
+  known!(0x2DC8, 0x3106, "8BitDo", "Ultimate / Pro 2 Controller", []),
+  known!(0x24C6, 0x543A, "PowerA", "Xbox One Wired Controller", [ImpulseTriggersUnavailable]),
+  known!(0x0F0D, 0x0067, "Hori", "HORIPAD ONE", []),
+  known!(0x0738, 0x4716, "Mad Catz", "Wired Xbox 360 Controller", []),
+  known!(0x28DE, 0x11FF, "Valve", "Steam Virtual Gamepad", [VirtualDevice]),
+];
+
+/// The USB IDs of a controller, as reported by `get_capabilities_ex`.
+///
+/// Only some versions of XInput fill these in, so you might get all zeroes.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct ControllerIdentity {
+  /// USB Vendor ID
+  pub vendor_id: u16,
+  /// USB Product ID
+  pub product_id: u16,
+  /// USB Revision ID
+  pub revision_id: u16,
+}
+
+impl From<XINPUT_CAPABILITIES_EX> for ControllerIdentity {
+  fn from(caps: XINPUT_CAPABILITIES_EX) -> Self {
+    ControllerIdentity {
+      vendor_id: caps.vendor_id,
+      product_id: caps.product_id,
+      revision_id: caps.revision_id,
+    }
+  }
+}
+
+impl Display for ControllerIdentity {
+  /// Formats as `VVVV:PPPP` in hex, the way USB IDs are usually written.
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    write!(f, "{:04X}:{:04X}", self.vendor_id, self.product_id)
+  }
+}
+
+impl ControllerIdentity {
+  /// If the DLL didn't fill in any IDs at all.
+  pub fn is_unknown(&self) -> bool {
+    self.vendor_id == 0 && self.product_id == 0
+  }
+
+  /// Looks up this controller in `KNOWN_CONTROLLERS`.
+  ///
+  /// The revision isn't used for the lookup.
+  pub fn known_controller(&self) -> Option<&'static KnownController> {
+    KNOWN_CONTROLLERS
+      .iter()
+      .find(|known| known.vendor_id == self.vendor_id && known.product_id == self.product_id)
+  }
+
+  /// The name of this controller, if it's a known model.
+  pub fn name(&self) -> Option<&'static str> {
+    self.known_controller().map(|known| known.name)
+  }
+
+  /// The quirks of this controller. Unknown models have no known quirks.
+  pub fn quirks(&self) -> &'static [Quirk] {
+    self
+      .known_controller()
+      .map(|known| known.quirks)
+      .unwrap_or(&[])
+  }
+
+  /// If this controller has the quirk given.
+  pub fn has_quirk(&self, quirk: Quirk) -> bool {
+    self.quirks().contains(&quirk)
+  }
+}
+
+#[test]
+fn controller_identity_test() {
+  let f310 = ControllerIdentity {
+    vendor_id: 0x046D,
+    product_id: 0xC21D,
+    revision_id: 0x4014,
+  };
+  assert_eq!(f310.to_string(), "046D:C21D");
+  assert_eq!(f310.name(), Some("Gamepad F310"));
+  assert!(f310.has_quirk(Quirk::NoRumble));
+  assert!(!f310.has_quirk(Quirk::SharedIdentity));
+
+  let unknown = ControllerIdentity {
+    vendor_id: 0,
+    product_id: 0,
+    revision_id: 0,
+  };
+  assert!(unknown.is_unknown());
+  assert_eq!(unknown.known_controller(), None);
+  assert!(unknown.quirks().is_empty());
+
+  for (i, a) in KNOWN_CONTROLLERS.iter().enumerate() {
+    for b in &KNOWN_CONTROLLERS[i + 1..] {
+      assert!((a.vendor_id, a.product_id) != (b.vendor_id, b.product_id));
+    }
+  }
+}
@@ -21,29 +21,111 @@
 //!
 //! Note that there are theoretically other XInput extras you might care about,
 //! but they're only available in Windows 8+ and I use Windows 7, so oh well.
+//!
+//! ## Other Platforms
+//!
+//! Only the DLL loading (`XInputHandle` and friends) is Windows specific. The
+//! state types, the error types, the stick math, and the `MockBackend` are
+//! available everywhere, so code that uses them can be built and tested on any
+//! platform.
+//!
+//! ## Features
+//!
+//! * `serde`: The input settings types (`ResponseCurve`, `StickDeadzone`, and
+//!   so on) can be serialized and deserialized.
 
 #![allow(non_upper_case_globals)]
 #![warn(missing_docs)]
 #![forbid(missing_debug_implementations)]
-#![cfg(windows)]
 
 #[macro_use]
 extern crate log;
 
+#[cfg(feature = "serde")]
+extern crate serde;
+
+#[cfg(windows)]
 #[macro_use]
 extern crate lazy_static;
 
+#[cfg(windows)]
 extern crate winapi;
 
+#[cfg(windows)]
+use std::ffi::{OsStr, OsString};
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(windows)]
+use std::sync::Arc;
+
+#[cfg(windows)]
 use winapi::shared::guiddef::GUID;
-use winapi::shared::minwindef::{BOOL, BYTE, DWORD, HMODULE, UINT, WORD};
+#[cfg(windows)]
+use winapi::shared::minwindef::{BOOL, BYTE, DWORD, HMODULE, UINT};
+#[cfg(windows)]
 use winapi::shared::ntdef::LPWSTR;
+#[cfg(windows)]
 use winapi::shared::winerror::{ERROR_DEVICE_NOT_CONNECTED, ERROR_EMPTY, ERROR_SUCCESS};
-use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryW};
-use winapi::um::xinput::*;
+#[cfg(windows)]
+use winapi::um::errhandlingapi::GetLastError;
+#[cfg(windows)]
+use winapi::um::libloaderapi::{FreeLibrary, GetProcAddress, LoadLibraryW};
+
+mod raw;
+pub use raw::*;
+
+mod backend;
+pub use backend::{ControllerBackend, MockBackend};
+
+mod record;
+pub use record::{read_recording, InputRecorder, RecordedFrame, RECORDING_VERSION};
+
+mod replay;
+pub use replay::{ReplayBackend, ReplayMode};
+
+mod buttons;
+pub use buttons::{Button, Buttons, ButtonsIter};
+
+mod delta;
+pub use delta::{Edge, StateDelta};
+
+mod stick;
+pub use stick::{AngularSnap, DeadzoneMode, StickDeadzone};
+
+mod curve;
+pub use curve::ResponseCurve;
+
+mod trigger;
+pub use trigger::{TriggerButton, TriggerDeadzone};
+
+mod calibration;
+pub use calibration::{StickCalibration, StickCalibrator};
+
+mod direction;
+pub use direction::{Direction4, Direction8, DirectionQuantizer};
+
+mod gesture;
+pub use gesture::{Gesture, GestureDetector};
+
+mod motion;
+pub use motion::{Facing, MotionMatch, MotionParser, MotionPattern, MotionShape, MotionStep};
+
+mod manager;
+pub use manager::{ControllerEvent, ControllerManager};
+
+mod polling;
+pub use polling::{PollingEvent, PollingEventKind, PollingThread, MIN_POLLING_INTERVAL};
+
+mod identity;
+pub use identity::{ControllerIdentity, KnownController, Quirk, KNOWN_CONTROLLERS};
+
+#[cfg(windows)]
+mod loader;
+#[cfg(windows)]
+pub use loader::XInputLoader;
 
 /// GetStateEx can get this in wButton
-pub const XINPUT_GAMEPAD_GUIDE: winapi::shared::minwindef::WORD = 0x0400;
+pub const XINPUT_GAMEPAD_GUIDE: u16 = 0x0400;
 
 /// Capabilities info from the undocumented `XInputGetCapabilitiesEx` fn.
 #[repr(C)]
@@ -53,52 +135,98 @@ pub struct XINPUT_CAPABILITIES_EX {
   /// The wrapped "basic capabilities" value
   pub capabilities: XINPUT_CAPABILITIES,
   /// USB Vendor ID of the attached controller
-  pub vendor_id: WORD,
+  pub vendor_id: u16,
   /// USB Product ID of the attached controller
-  pub product_id: WORD,
+  pub product_id: u16,
   /// USB Revision ID of the attached controller
-  pub revision_id: WORD,
+  pub revision_id: u16,
   /// unknown use
-  pub a4: DWORD,
+  pub a4: u32,
 }
 impl ::std::fmt::Debug for XINPUT_CAPABILITIES_EX {
   fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-    write!(f, "XINPUT_CAPABILITIES_EX (_)")
+    f.debug_struct("XINPUT_CAPABILITIES_EX")
+      .field("capabilities", &self.capabilities)
+      .field("vendor_id", &self.vendor_id)
+      .field("product_id", &self.product_id)
+      .field("revision_id", &self.revision_id)
+      .finish()
   }
 }
 
 use std::fmt::{self, Debug, Formatter};
 
+#[cfg(windows)]
 type XInputEnableFunc = unsafe extern "system" fn(BOOL);
+#[cfg(windows)]
 type XInputGetStateFunc = unsafe extern "system" fn(DWORD, *mut XINPUT_STATE) -> DWORD;
+#[cfg(windows)]
 type XInputSetStateFunc = unsafe extern "system" fn(DWORD, *mut XINPUT_VIBRATION) -> DWORD;
+#[cfg(windows)]
 type XInputGetCapabilitiesFunc =
   unsafe extern "system" fn(DWORD, DWORD, *mut XINPUT_CAPABILITIES) -> DWORD;
 
 // undocumented
+#[cfg(windows)]
 type XInputGetStateExFunc = unsafe extern "system" fn(DWORD, *mut XINPUT_STATE) -> DWORD;
 
 // undocumented
+#[cfg(windows)]
 type XInputGetCapabilitiesEx =
   unsafe extern "system" fn(DWORD, DWORD, DWORD, *mut XINPUT_CAPABILITIES_EX) -> DWORD;
 
 // **Removed** in xinput1_4.dll.
+#[cfg(windows)]
 type XInputGetDSoundAudioDeviceGuidsFunc =
   unsafe extern "system" fn(DWORD, *mut GUID, *mut GUID) -> DWORD;
 
 // Added in xinput1_3.dll.
-type XInputGetKeystrokeFunc = unsafe extern "system" fn(DWORD, DWORD, PXINPUT_KEYSTROKE) -> DWORD;
+#[cfg(windows)]
+type XInputGetKeystrokeFunc =
+  unsafe extern "system" fn(DWORD, DWORD, *mut XINPUT_KEYSTROKE) -> DWORD;
+#[cfg(windows)]
 type XInputGetBatteryInformationFunc =
   unsafe extern "system" fn(DWORD, BYTE, *mut XINPUT_BATTERY_INFORMATION) -> DWORD;
 
 // Added in xinput1_4.dll.
+#[cfg(windows)]
 type XInputGetAudioDeviceIdsFunc =
   unsafe extern "system" fn(DWORD, LPWSTR, *mut UINT, LPWSTR, *mut UINT) -> DWORD;
 
+/// Owns one reference to a loaded DLL, which is released on drop.
+#[cfg(windows)]
+struct LoadedModule {
+  handle: HMODULE,
+  path: OsString,
+  dll_name: String,
+}
+
+#[cfg(windows)]
+impl Drop for LoadedModule {
+  fn drop(&mut self) {
+    trace!("Freeing XInput DLL: {:?}", self.dll_name);
+    // We only ever make a `LoadedModule` from a successful `LoadLibraryW`, and
+    // the function pointers that point into the DLL are always dropped along
+    // with (or before) the last `Arc` to this.
+    unsafe { FreeLibrary(self.handle) };
+  }
+}
+
+// The module handle is just an address, and XInput's functions are documented
+// as being safe to call from any thread.
+#[cfg(windows)]
+unsafe impl Send for LoadedModule {}
+#[cfg(windows)]
+unsafe impl Sync for LoadedModule {}
+
 /// A handle to a loaded XInput DLL.
+///
+/// Cloning a handle is cheap, and all clones share the same loaded DLL. The
+/// DLL is unloaded when the last clone is dropped.
+#[cfg(windows)]
 #[derive(Clone)]
 pub struct XInputHandle {
-  handle: HMODULE,
+  module: Arc<LoadedModule>,
   xinput_enable: XInputEnableFunc,
   xinput_get_state: XInputGetStateFunc,
   xinput_set_state: XInputSetStateFunc,
@@ -107,55 +235,34 @@ pub struct XInputHandle {
   opt_xinput_get_capabilities_ex: Option<XInputGetCapabilitiesEx>,
   opt_xinput_get_keystroke: Option<XInputGetKeystrokeFunc>,
   opt_xinput_get_battery_information: Option<XInputGetBatteryInformationFunc>,
-  // some day we should use these
-  _opt_xinput_get_audio_device_ids: Option<XInputGetAudioDeviceIdsFunc>,
-  _opt_xinput_get_dsound_audio_device_guids: Option<XInputGetDSoundAudioDeviceGuidsFunc>,
+  opt_xinput_get_audio_device_ids: Option<XInputGetAudioDeviceIdsFunc>,
+  opt_xinput_get_dsound_audio_device_guids: Option<XInputGetDSoundAudioDeviceGuidsFunc>,
 }
 
+#[cfg(windows)]
 impl Debug for XInputHandle {
   fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
-    write!(f, "XInputHandle(handle = {:?})", self.handle)
+    write!(
+      f,
+      "XInputHandle(dll_name = {:?}, handle = {:?})",
+      self.module.dll_name, self.module.handle
+    )
   }
 }
 
-unsafe impl Send for XInputHandle {}
-unsafe impl Sync for XInputHandle {}
-
+#[cfg(windows)]
 lazy_static! {
   static ref GLOBAL_XINPUT_HANDLE: Result<XInputHandle, XInputLoadingFailure> =
     XInputHandle::load_default();
 }
 
-/// Quick and dirty wrapper to let us format log messages easier.
-pub(crate) struct WideNullU16<'a>(&'a [u16; ::winapi::shared::minwindef::MAX_PATH]);
-impl<'a> ::std::fmt::Debug for WideNullU16<'a> {
-  fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-    for &u in self.0.iter() {
-      if u == 0 {
-        break;
-      } else {
-        write!(f, "{}", u as u8 as char)?
-      }
-    }
-    Ok(())
-  }
-}
-
-/// Converts a rusty string into a win32 string.
-pub(crate) fn wide_null<S: AsRef<str>>(s: S) -> [u16; ::winapi::shared::minwindef::MAX_PATH] {
-  let mut output: [u16; ::winapi::shared::minwindef::MAX_PATH] =
-    [0; ::winapi::shared::minwindef::MAX_PATH];
-  let mut i = 0;
-  for u in s.as_ref().encode_utf16() {
-    if i == output.len() - 1 {
-      break;
-    } else {
-      output[i] = u;
-    }
-    i += 1;
-  }
-  output[i] = 0;
-  output
+/// Converts a name or path into a null terminated win32 string.
+///
+/// There's no length limit here, so long paths are passed along whole, and it's
+/// up to Windows to decide if it can open them.
+#[cfg(windows)]
+pub(crate) fn wide_null<S: AsRef<OsStr>>(s: S) -> Vec<u16> {
+  s.as_ref().encode_wide().chain(Some(0)).collect()
 }
 
 /// The ways that a dynamic load of XInput can fail.
@@ -183,15 +290,102 @@ pub enum XInputLoadingFailure {
   NoPointers,
 }
 
+/// The result of trying to load one particular XInput DLL.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct XInputLoadAttempt {
+  /// The DLL name that was tried.
+  pub dll_name: String,
+  /// `NoDLL` if the DLL itself couldn't be loaded, or `NoPointers` if it
+  /// loaded but didn't have all the required functions.
+  pub failure: XInputLoadingFailure,
+  /// The `GetLastError` code from when loading the DLL failed, if that's where
+  /// things went wrong. Code 126 (`ERROR_MOD_NOT_FOUND`) is the usual "this DLL
+  /// just isn't there" code, anything else means that something is actually
+  /// broken.
+  pub os_error: Option<u32>,
+}
+
+impl fmt::Display for XInputLoadAttempt {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    write!(f, "{}: {:?}", self.dll_name, self.failure)?;
+    if let Some(code) = self.os_error {
+      write!(f, " (os error {})", code)?;
+    }
+    Ok(())
+  }
+}
+
+/// Explains why no XInput DLL could be loaded, one attempt per DLL tried.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct XInputLoadError {
+  /// Every DLL that was tried, in the order they were tried.
+  pub attempts: Vec<XInputLoadAttempt>,
+}
+
+impl fmt::Display for XInputLoadError {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    write!(f, "XInput could not be loaded")?;
+    for (i, attempt) in self.attempts.iter().enumerate() {
+      write!(f, "{} {}", if i == 0 { ":" } else { "," }, attempt)?;
+    }
+    Ok(())
+  }
+}
+
+impl ::std::error::Error for XInputLoadError {}
+
+impl XInputLoadError {
+  /// Sums up the attempts as a single `XInputLoadingFailure`.
+  ///
+  /// This is `NoPointers` if any DLL was found but was missing functions, and
+  /// `NoDLL` if none of them could be loaded at all.
+  pub fn failure(&self) -> XInputLoadingFailure {
+    let no_pointers = self
+      .attempts
+      .iter()
+      .any(|attempt| attempt.failure == XInputLoadingFailure::NoPointers);
+    if no_pointers {
+      XInputLoadingFailure::NoPointers
+    } else {
+      XInputLoadingFailure::NoDLL
+    }
+  }
+}
+
+#[test]
+fn load_error_failure_test() {
+  let attempt = |dll_name: &str, failure| XInputLoadAttempt {
+    dll_name: dll_name.to_string(),
+    failure,
+    os_error: None,
+  };
+  let mut err = XInputLoadError {
+    attempts: vec![
+      attempt("xinput1_4.dll", XInputLoadingFailure::NoDLL),
+      attempt("xinput1_3.dll", XInputLoadingFailure::NoDLL),
+    ],
+  };
+  assert_eq!(err.failure(), XInputLoadingFailure::NoDLL);
+  err.attempts[1].failure = XInputLoadingFailure::NoPointers;
+  assert_eq!(err.failure(), XInputLoadingFailure::NoPointers);
+  assert_eq!(
+    XInputLoadError { attempts: vec![] }.failure(),
+    XInputLoadingFailure::NoDLL
+  );
+}
+
+#[cfg(windows)]
 impl XInputHandle {
   /// Attempts to dynamically load an XInput DLL and get the function pointers.
   ///
   /// # Failure
   ///
-  /// This can fail in a few ways, as explained in the `XInputLoadingFailure`
-  /// type. The most likely failure case is that the user's system won't have the
-  /// required DLL, in which case you should probably allow them to play with just
-  /// a keyboard/mouse instead.
+  /// If none of the DLLs can be loaded you get back `NoPointers` if one of
+  /// them was missing functions, or `NoDLL` otherwise. Use
+  /// `load_default_detailed` to see what went wrong with each DLL. The most
+  /// likely failure case is that the user's system won't have the required
+  /// DLL, in which case you should probably allow them to play with just a
+  /// keyboard/mouse instead.
   ///
   /// # Current DLL Names
   ///
@@ -202,36 +396,72 @@ impl XInputHandle {
   /// * `xinput1_2.dll`
   /// * `xinput1_1.dll`
   /// * `xinput9_1_0.dll`
+  ///
+  /// If you need some other search order, use an `XInputLoader`.
   pub fn load_default() -> Result<XInputHandle, XInputLoadingFailure> {
-    let xinput14 = "xinput1_4.dll";
-    let xinput13 = "xinput1_3.dll";
-    let xinput12 = "xinput1_2.dll";
-    let xinput11 = "xinput1_1.dll";
-    let xinput91 = "xinput9_1_0.dll";
-
-    for lib_name in [xinput14, xinput13, xinput12, xinput11, xinput91] {
-      if let Ok(handle) = XInputHandle::load(lib_name) {
-        return Ok(handle);
+    XInputHandle::load_default_detailed().map_err(|err| err.failure())
+  }
+
+  /// As `load_default`, but if none of the DLLs can be loaded you get back an
+  /// `XInputLoadError` that says what went wrong with each one.
+  pub fn load_default_detailed() -> Result<XInputHandle, XInputLoadError> {
+    let mut attempts = Vec::new();
+    for lib_name in XInputHandle::DEFAULT_DLL_NAMES.iter() {
+      match XInputHandle::load_attempt(lib_name) {
+        Ok(handle) => return Ok(handle),
+        Err(attempt) => attempts.push(attempt),
       }
     }
 
-    debug!("Failure: XInput could not be loaded.");
-    Err(XInputLoadingFailure::NoDLL)
+    let err = XInputLoadError { attempts };
+    debug!("Failure: {}", err);
+    Err(err)
   }
 
+  /// The DLL names that `load_default` searches for, in order.
+  pub const DEFAULT_DLL_NAMES: [&'static str; 5] =
+    ["xinput1_4.dll", "xinput1_3.dll", "xinput1_2.dll", "xinput1_1.dll", "xinput9_1_0.dll"];
+
   /// Attempt to load a specific XInput DLL and get the function pointers.
-  pub fn load<S: AsRef<str>>(s: S) -> Result<XInputHandle, XInputLoadingFailure> {
-    let lib_name = wide_null(s);
-    trace!(
-      "Attempting to load XInput DLL: {:?}",
-      WideNullU16(&lib_name)
-    );
+  ///
+  /// This can be a plain DLL name, in which case the normal Windows DLL search
+  /// order applies, or a full path to a DLL.
+  ///
+  /// # Failure
+  ///
+  /// `NoDLL` if the DLL couldn't be loaded, or `NoPointers` if it didn't have
+  /// the required functions.
+  pub fn load<S: AsRef<OsStr>>(s: S) -> Result<XInputHandle, XInputLoadingFailure> {
+    XInputHandle::load_attempt(s).map_err(|attempt| attempt.failure)
+  }
+
+  pub(crate) fn load_attempt<S: AsRef<OsStr>>(s: S) -> Result<XInputHandle, XInputLoadAttempt> {
+    let path = s.as_ref().to_os_string();
+    let dll_name = path.to_string_lossy().into_owned();
+    let lib_name = wide_null(&path);
+    trace!("Attempting to load XInput DLL: {:?}", dll_name);
     // It's always safe to call `LoadLibraryW`, the worst that can happen is
     // that we get a null pointer back.
     let xinput_handle = unsafe { LoadLibraryW(lib_name.as_ptr()) };
-    if !xinput_handle.is_null() {
-      debug!("Success: XInput Loaded: {:?}", WideNullU16(&lib_name));
+    if xinput_handle.is_null() {
+      let os_error = unsafe { GetLastError() };
+      debug!(
+        "Could not load XInput DLL: {:?} (os error {})",
+        dll_name, os_error
+      );
+      return Err(XInputLoadAttempt {
+        dll_name,
+        failure: XInputLoadingFailure::NoDLL,
+        os_error: Some(os_error),
+      });
     }
+    debug!("Success: XInput Loaded: {:?}", dll_name);
+    // From here on, an early return frees the DLL again.
+    let module = LoadedModule {
+      handle: xinput_handle,
+      path,
+      dll_name,
+    };
 
     let enable_name = b"XInputEnable\0";
     let get_state_name = b"XInputGetState\0";
@@ -308,7 +538,7 @@ impl XInputHandle {
       let get_capabilities_ptr =
         GetProcAddress(xinput_handle, 108_i32 as winapi::um::winnt::LPCSTR);
       if !get_capabilities_ptr.is_null() {
-        trace!("Found XInputGetCapabilities.");
+        trace!("Found XInputGetCapabilitiesEx.");
         opt_xinput_get_capabilities_ex = Some(::std::mem::transmute(get_capabilities_ptr));
       } else {
         trace!("Could not find XInputGetCapabilitiesEx.");
@@ -372,7 +602,7 @@ impl XInputHandle {
     {
       debug!("All function pointers loaded successfully.");
       Ok(XInputHandle {
-        handle: xinput_handle,
+        module: Arc::new(module),
         xinput_enable: opt_xinput_enable.unwrap(),
         xinput_get_state: opt_xinput_get_state.unwrap(),
         xinput_set_state: opt_xinput_set_state.unwrap(),
@@ -381,16 +611,81 @@ impl XInputHandle {
         opt_xinput_get_state_ex,
         opt_xinput_get_keystroke,
         opt_xinput_get_battery_information,
-        _opt_xinput_get_dsound_audio_device_guids: opt_xinput_get_dsound_audio_device_guids,
-        _opt_xinput_get_audio_device_ids: opt_xinput_get_audio_device_ids,
+        opt_xinput_get_dsound_audio_device_guids,
+        opt_xinput_get_audio_device_ids,
       })
     } else {
       debug!("Could not load the function pointers.");
-      Err(XInputLoadingFailure::NoPointers)
+      Err(XInputLoadAttempt {
+        dll_name: module.dll_name.clone(),
+        failure: XInputLoadingFailure::NoPointers,
+        os_error: None,
+      })
+    }
+  }
+
+  /// Drops this handle and then loads the same DLL again.
+  ///
+  /// This is for hot-swapping the DLL during development. If this was the last
+  /// handle to the DLL then the DLL is fully unloaded before the new load
+  /// happens, so you can replace the file in between. If other clones of the
+  /// handle are still alive then Windows keeps the old DLL loaded and you'll
+  /// just get that same DLL back.
+  ///
+  /// # Failure
+  ///
+  /// The same as with `load`. The old handle is gone either way.
+  pub fn reload(self) -> Result<XInputHandle, XInputLoadingFailure> {
+    let path = self.module.path.clone();
+    drop(self);
+    XInputHandle::load(path)
+  }
+
+  /// Reports the DLL that was loaded and which of the optional XInput
+  /// functions it provides.
+  pub fn features(&self) -> XInputFeatures {
+    XInputFeatures {
+      dll_name: self.module.dll_name.clone(),
+      get_state_ex: self.opt_xinput_get_state_ex.is_some(),
+      get_capabilities_ex: self.opt_xinput_get_capabilities_ex.is_some(),
+      get_keystroke: self.opt_xinput_get_keystroke.is_some(),
+      get_battery_information: self.opt_xinput_get_battery_information.is_some(),
+      get_audio_device_ids: self.opt_xinput_get_audio_device_ids.is_some(),
+      get_dsound_audio_device_guids: self.opt_xinput_get_dsound_audio_device_guids.is_some(),
     }
   }
 }
 
+/// Describes which optional XInput functions a loaded DLL has.
+///
+/// The basic functions (`XInputEnable`, `XInputGetState`, `XInputSetState`, and
+/// `XInputGetCapabilities`) are required for a load to succeed at all, so
+/// they're always available and aren't listed here. Everything else depends on
+/// the DLL version, and the undocumented functions can be missing from any
+/// version.
+///
+/// This is plain data so that you can log it, send it off with telemetry, or
+/// show it on a diagnostics screen.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct XInputFeatures {
+  /// The name of the DLL that was loaded, as it was given to `load`.
+  pub dll_name: String,
+  /// The undocumented `XInputGetStateEx` (ordinal 100). Without this,
+  /// `get_state_ex` fails and there's no way to read the guide button.
+  pub get_state_ex: bool,
+  /// The undocumented `XInputGetCapabilitiesEx` (ordinal 108). Without this,
+  /// `get_capabilities_ex` fails.
+  pub get_capabilities_ex: bool,
+  /// `XInputGetKeystroke`, added in `xinput1_3.dll`.
+  pub get_keystroke: bool,
+  /// `XInputGetBatteryInformation`, added in `xinput1_3.dll`.
+  pub get_battery_information: bool,
+  /// `XInputGetAudioDeviceIds`, added in `xinput1_4.dll`.
+  pub get_audio_device_ids: bool,
+  /// `XInputGetDSoundAudioDeviceGuids`, removed in `xinput1_4.dll`.
+  pub get_dsound_audio_device_guids: bool,
+}
+
 /// Attempts to dynamically load an XInput DLL and get the function pointers.
 ///
 /// This operation is thread-safe and can be performed at any time. If xinput
@@ -398,9 +693,9 @@ impl XInputHandle {
 /// `xinput_get_state` and `xinput_set_state` will safety return an `Err` value
 /// to that effect.
 ///
-/// There's no way provided to unload XInput once it's been loaded, because that
-/// makes the normal operation a little faster. Why would you want to unload it
-/// anyway? Don't be silly.
+/// There's no way provided to unload this global copy of XInput once it's been
+/// loaded. If you want to control when the DLL is unloaded, use your own
+/// `XInputHandle` values instead.
 ///
 /// # Failure
 ///
@@ -418,6 +713,7 @@ impl XInputHandle {
 /// * `xinput1_3.dll`
 /// * `xinput1_2.dll`
 /// * `xinput1_1.dll`
+#[cfg(windows)]
 #[deprecated]
 pub fn dynamic_load_xinput() -> Result<(), XInputLoadingFailure> {
   if let Err(err) = *GLOBAL_XINPUT_HANDLE {
@@ -450,21 +746,11 @@ pub struct XInputState {
 
 impl ::std::default::Default for XInputState {
   #[inline]
+  #[allow(unused_attributes)]
   #[must_use]
   fn default() -> Self {
     Self {
-      raw: XINPUT_STATE {
-        dwPacketNumber: 0,
-        Gamepad: XINPUT_GAMEPAD {
-          wButtons: 0,
-          bLeftTrigger: 0,
-          bRightTrigger: 0,
-          sThumbLX: 0,
-          sThumbLY: 0,
-          sThumbRX: 0,
-          sThumbRY: 0,
-        },
-      },
+      raw: XINPUT_STATE::default(),
     }
   }
 }
@@ -488,6 +774,12 @@ impl ::std::fmt::Debug for XInputState {
 }
 
 impl XInputState {
+  /// All the buttons that are currently pressed, as a set.
+  #[inline]
+  pub fn buttons(&self) -> Buttons {
+    Buttons::from(self.raw.Gamepad.wButtons)
+  }
+
   /// The north button of the action button group.
   ///
   /// * Nintendo: X
@@ -552,6 +844,19 @@ impl XInputState {
     self.raw.Gamepad.wButtons & XINPUT_GAMEPAD_DPAD_RIGHT != 0
   }
 
+  /// The D-pad as a single direction.
+  ///
+  /// Pressing two opposite directions at once cancels both of them out.
+  #[inline]
+  pub fn dpad_direction(&self) -> Direction8 {
+    Direction8::from_dpad(
+      self.arrow_up(),
+      self.arrow_down(),
+      self.arrow_left(),
+      self.arrow_right(),
+    )
+  }
+
   /// The "start" button.
   ///
   /// * Nintendo: Start (NES / SNES), '+' (Pro Controller)
@@ -649,6 +954,38 @@ impl XInputState {
     self.right_trigger() >= XInputState::TRIGGER_THRESHOLD
   }
 
+  /// The lower left shoulder trigger from 0.0 to 1.0, using the default
+  /// deadzone.
+  ///
+  /// See `TriggerDeadzone::DEFAULT` for more.
+  #[inline]
+  pub fn left_trigger_normalized(&self) -> f32 {
+    TriggerDeadzone::DEFAULT.apply(self.left_trigger())
+  }
+
+  /// The lower right shoulder trigger from 0.0 to 1.0, using the default
+  /// deadzone.
+  ///
+  /// See `TriggerDeadzone::DEFAULT` for more.
+  #[inline]
+  pub fn right_trigger_normalized(&self) -> f32 {
+    TriggerDeadzone::DEFAULT.apply(self.right_trigger())
+  }
+
+  /// The lower left shoulder trigger from 0.0 to 1.0, using the deadzone
+  /// given.
+  #[inline]
+  pub fn left_trigger_normalized_with(&self, deadzone: &TriggerDeadzone) -> f32 {
+    deadzone.apply(self.left_trigger())
+  }
+
+  /// The lower right shoulder trigger from 0.0 to 1.0, using the deadzone
+  /// given.
+  #[inline]
+  pub fn right_trigger_normalized_with(&self, deadzone: &TriggerDeadzone) -> f32 {
+    deadzone.apply(self.right_trigger())
+  }
+
   /// The left thumb stick being pressed inward.
   ///
   /// * Nintendo: (L)
@@ -710,16 +1047,34 @@ impl XInputState {
     )
   }
 
+  /// The left stick value normalized with the deadzone given.
+  ///
+  /// See `StickDeadzone` for the options.
+  #[inline]
+  pub fn left_stick_normalized_with(&self, deadzone: &StickDeadzone) -> (f32, f32) {
+    deadzone.apply(self.left_stick_raw())
+  }
+
+  /// The right stick value normalized with the deadzone given.
+  ///
+  /// See `StickDeadzone` for the options.
+  #[inline]
+  pub fn right_stick_normalized_with(&self, deadzone: &StickDeadzone) -> (f32, f32) {
+    deadzone.apply(self.right_stick_raw())
+  }
+
   /// This helper normalizes a raw stick value using the given deadzone.
   ///
   /// If the raw value's 2d length is less than the deadzone the result will be
   /// `(0.0,0.0)`, otherwise the result is normalized across the range from the
-  /// deadzone point to the maximum value.
+  /// deadzone point to the maximum value. This is the same as a `StickDeadzone`
+  /// using `DeadzoneMode::ScaledRadial`, which also has other modes.
   ///
   /// The `deadzone` value is clamped to the range 0 to 32,766 (inclusive)
   /// before use. Negative inputs or maximum value inputs make the normalization
   /// just work improperly.
   #[inline]
+  #[allow(clippy::manual_clamp, clippy::legacy_numeric_constants)]
   pub fn normalize_raw_stick_value(raw_stick: (i16, i16), deadzone: i16) -> (f32, f32) {
     let deadzone_float = deadzone.max(0).min(i16::max_value() - 1) as f32;
     let raw_float = (raw_stick.0 as f32, raw_stick.1 as f32);
@@ -738,6 +1093,7 @@ impl XInputState {
 
 #[test]
 #[rustfmt::skip]
+#[allow(clippy::legacy_numeric_constants)]
 fn normalize_raw_stick_value_test() {
   for x in [i16::min_value(), i16::max_value()] {
     for y in [i16::min_value(), i16::max_value()] {
@@ -783,6 +1139,7 @@ pub enum XInputOptionalFnUsageError {
   UnknownError(u32),
 }
 
+#[cfg(windows)]
 impl XInputHandle {
   /// Enables or disables XInput.
   ///
@@ -864,6 +1221,7 @@ impl XInputHandle {
 }
 
 /// See `XInputHandle::get_state`
+#[cfg(windows)]
 #[deprecated]
 pub fn xinput_get_state(user_index: u32) -> Result<XInputState, XInputUsageError> {
   match *GLOBAL_XINPUT_HANDLE {
@@ -872,6 +1230,7 @@ pub fn xinput_get_state(user_index: u32) -> Result<XInputState, XInputUsageError
   }
 }
 
+#[cfg(windows)]
 impl XInputHandle {
   /// Allows you to set the rumble speeds of the left and right motors.
   ///
@@ -918,6 +1277,7 @@ impl XInputHandle {
 }
 
 /// See `XInputHandle::set_state`
+#[cfg(windows)]
 #[deprecated]
 pub fn xinput_set_state(
   user_index: u32,
@@ -930,6 +1290,184 @@ pub fn xinput_set_state(
   }
 }
 
+/// The specific kind of device that a controller is.
+///
+/// XInput maps every device onto the gamepad layout, but the subtype lets you
+/// pick a control scheme that suits the device. Older drivers will often just
+/// say `Gamepad` (or `Unknown`) no matter what's plugged in, so always have a
+/// sensible default.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum DeviceSubType {
+  /// The subtype isn't known.
+  Unknown,
+  /// A normal gamepad.
+  Gamepad,
+  /// A racing wheel.
+  Wheel,
+  /// An arcade stick.
+  ArcadeStick,
+  /// A flight stick.
+  FlightStick,
+  /// A dance pad.
+  DancePad,
+  /// A guitar.
+  Guitar,
+  /// An alternate guitar, which has a more limited set of inputs.
+  GuitarAlternate,
+  /// A bass guitar.
+  GuitarBass,
+  /// A drum kit.
+  DrumKit,
+  /// An arcade pad.
+  ArcadePad,
+  /// Some value that this crate doesn't know about.
+  Other(u8),
+}
+
+impl From<u8> for DeviceSubType {
+  fn from(sub_type: u8) -> Self {
+    match sub_type {
+      XINPUT_DEVSUBTYPE_UNKNOWN => DeviceSubType::Unknown,
+      XINPUT_DEVSUBTYPE_GAMEPAD => DeviceSubType::Gamepad,
+      XINPUT_DEVSUBTYPE_WHEEL => DeviceSubType::Wheel,
+      XINPUT_DEVSUBTYPE_ARCADE_STICK => DeviceSubType::ArcadeStick,
+      XINPUT_DEVSUBTYPE_FLIGHT_STICK => DeviceSubType::FlightStick,
+      XINPUT_DEVSUBTYPE_DANCE_PAD => DeviceSubType::DancePad,
+      XINPUT_DEVSUBTYPE_GUITAR => DeviceSubType::Guitar,
+      XINPUT_DEVSUBTYPE_GUITAR_ALTERNATE => DeviceSubType::GuitarAlternate,
+      XINPUT_DEVSUBTYPE_GUITAR_BASS => DeviceSubType::GuitarBass,
+      XINPUT_DEVSUBTYPE_DRUM_KIT => DeviceSubType::DrumKit,
+      XINPUT_DEVSUBTYPE_ARCADE_PAD => DeviceSubType::ArcadePad,
+      other => DeviceSubType::Other(other),
+    }
+  }
+}
+
+impl From<DeviceSubType> for u8 {
+  fn from(sub_type: DeviceSubType) -> Self {
+    match sub_type {
+      DeviceSubType::Unknown => XINPUT_DEVSUBTYPE_UNKNOWN,
+      DeviceSubType::Gamepad => XINPUT_DEVSUBTYPE_GAMEPAD,
+      DeviceSubType::Wheel => XINPUT_DEVSUBTYPE_WHEEL,
+      DeviceSubType::ArcadeStick => XINPUT_DEVSUBTYPE_ARCADE_STICK,
+      DeviceSubType::FlightStick => XINPUT_DEVSUBTYPE_FLIGHT_STICK,
+      DeviceSubType::DancePad => XINPUT_DEVSUBTYPE_DANCE_PAD,
+      DeviceSubType::Guitar => XINPUT_DEVSUBTYPE_GUITAR,
+      DeviceSubType::GuitarAlternate => XINPUT_DEVSUBTYPE_GUITAR_ALTERNATE,
+      DeviceSubType::GuitarBass => XINPUT_DEVSUBTYPE_GUITAR_BASS,
+      DeviceSubType::DrumKit => XINPUT_DEVSUBTYPE_DRUM_KIT,
+      DeviceSubType::ArcadePad => XINPUT_DEVSUBTYPE_ARCADE_PAD,
+      DeviceSubType::Other(other) => other,
+    }
+  }
+}
+
+impl DeviceSubType {
+  /// If this is any of the guitar subtypes.
+  #[inline]
+  pub fn is_guitar(self) -> bool {
+    matches!(
+      self,
+      DeviceSubType::Guitar | DeviceSubType::GuitarAlternate | DeviceSubType::GuitarBass
+    )
+  }
+}
+
+/// This wraps an `XINPUT_CAPABILITIES` value and decodes the type, subtype and
+/// flags for you.
+///
+/// If you want something that the rust wrapper doesn't support, just use the
+/// raw field to get at the inner value.
+#[derive(Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct XInputCapabilities {
+  /// The raw value we're wrapping.
+  pub raw: XINPUT_CAPABILITIES,
+}
+
+impl From<XINPUT_CAPABILITIES> for XInputCapabilities {
+  fn from(raw: XINPUT_CAPABILITIES) -> Self {
+    XInputCapabilities { raw }
+  }
+}
+
+impl XInputCapabilities {
+  /// The device type. This is always `XINPUT_DEVTYPE_GAMEPAD` at the moment.
+  #[inline]
+  pub fn device_type(&self) -> u8 {
+    self.raw.Type
+  }
+
+  /// The specific kind of device.
+  #[inline]
+  pub fn sub_type(&self) -> DeviceSubType {
+    DeviceSubType::from(self.raw.SubType)
+  }
+
+  /// If the device is wireless.
+  #[inline]
+  pub fn is_wireless(&self) -> bool {
+    self.raw.Flags & XINPUT_CAPS_WIRELESS != 0
+  }
+
+  /// If the device has an integrated voice device (eg: a headset port).
+  #[inline]
+  pub fn supports_voice(&self) -> bool {
+    self.raw.Flags & XINPUT_CAPS_VOICE_SUPPORTED != 0
+  }
+
+  /// If the device supports force feedback.
+  ///
+  /// This is a separate feature from the normal rumble motors, see
+  /// `supports_rumble` for those.
+  #[inline]
+  pub fn supports_force_feedback(&self) -> bool {
+    self.raw.Flags & XINPUT_CAPS_FFB_SUPPORTED != 0
+  }
+
+  /// If the device is missing the menu navigation buttons (start, back, and
+  /// the D-pad).
+  #[inline]
+  pub fn has_no_navigation(&self) -> bool {
+    self.raw.Flags & XINPUT_CAPS_NO_NAVIGATION != 0
+  }
+
+  /// If the device supports plug-in modules (eg: a chatpad).
+  #[inline]
+  pub fn supports_plugin_modules(&self) -> bool {
+    self.raw.Flags & XINPUT_CAPS_PMD_SUPPORTED != 0
+  }
+
+  /// If the device has at least one rumble motor.
+  #[inline]
+  pub fn supports_rumble(&self) -> bool {
+    self.raw.Vibration.wLeftMotorSpeed != 0 || self.raw.Vibration.wRightMotorSpeed != 0
+  }
+}
+
+#[test]
+fn capabilities_test() {
+  let mut raw = XINPUT_CAPABILITIES {
+    Type: XINPUT_DEVTYPE_GAMEPAD,
+    SubType: XINPUT_DEVSUBTYPE_GUITAR_BASS,
+    Flags: XINPUT_CAPS_WIRELESS | XINPUT_CAPS_NO_NAVIGATION,
+    ..XINPUT_CAPABILITIES::default()
+  };
+  let caps = XInputCapabilities::from(raw);
+  assert_eq!(caps.sub_type(), DeviceSubType::GuitarBass);
+  assert!(caps.sub_type().is_guitar());
+  assert!(caps.is_wireless() && caps.has_no_navigation());
+  assert!(!caps.supports_voice() && !caps.supports_force_feedback());
+  assert!(!caps.supports_plugin_modules() && !caps.supports_rumble());
+  raw.Vibration.wRightMotorSpeed = 0xFF;
+  assert!(XInputCapabilities::from(raw).supports_rumble());
+  assert_eq!(DeviceSubType::from(0x42), DeviceSubType::Other(0x42));
+  assert_eq!(
+    u8::from(DeviceSubType::ArcadePad),
+    XINPUT_DEVSUBTYPE_ARCADE_PAD
+  );
+}
+
+#[cfg(windows)]
 impl XInputHandle {
   /// Retrieve the capabilities of a controller.
   ///
@@ -952,10 +1490,24 @@ impl XInputHandle {
       }
     }
   }
+
+  /// As `get_capabilities`, but wrapped up as an `XInputCapabilities`.
+  pub fn get_capabilities_info(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputCapabilities, XInputUsageError> {
+    self
+      .get_capabilities(user_index)
+      .map(XInputCapabilities::from)
+  }
+
   /// Retrieve the Extended capabilities of a controller.
   ///
   /// Undocumented!! This isn't part of the official XInput API, but is often available.
   ///
+  /// Use `ControllerIdentity::from` on the result to find out which model of
+  /// controller it is.
+  ///
   /// ## Failure
   ///
   /// * This function is technically an undocumented API. If
@@ -1017,7 +1569,7 @@ impl XInputHandle {
 
 /// Defines type of battery used in device, if any.
 #[derive(Copy, Clone, Eq, PartialEq)]
-pub struct BatteryType(pub BYTE);
+pub struct BatteryType(pub u8);
 
 impl BatteryType {
   /// Device is disconnected.
@@ -1049,7 +1601,7 @@ impl Debug for BatteryType {
 
 /// Specify how much battery is charged for devices with battery.
 #[derive(Copy, Clone, Eq, PartialEq)]
-pub struct BatteryLevel(pub BYTE);
+pub struct BatteryLevel(pub u8);
 
 impl BatteryLevel {
   /// Battery is empty.
@@ -1087,6 +1639,7 @@ pub struct XInputBatteryInformation {
   pub battery_level: BatteryLevel,
 }
 
+#[cfg(windows)]
 impl XInputHandle {
   fn xinput_get_battery_information(
     &self,
@@ -1139,6 +1692,7 @@ impl XInputHandle {
 }
 
 /// See `InputHandle::get_gamepad_battery_information`
+#[cfg(windows)]
 #[deprecated]
 pub fn xinput_get_gamepad_battery_information(
   user_index: u32,
@@ -1150,6 +1704,7 @@ pub fn xinput_get_gamepad_battery_information(
 }
 
 /// See `InputHandle::get_headset_battery_information`
+#[cfg(windows)]
 #[deprecated]
 pub fn xinput_get_headset_battery_information(
   user_index: u32,
@@ -1159,3 +1714,176 @@ pub fn xinput_get_headset_battery_information(
     Err(_) => Err(XInputOptionalFnUsageError::XInputNotLoaded),
   }
 }
+
+/// The Windows audio device IDs of the headset plugged into a controller.
+///
+/// You can give these IDs to the Core Audio APIs (eg:
+/// `IMMDeviceEnumerator::GetDevice`) to play sound through the headset or
+/// record from its microphone.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct XInputAudioDeviceIds {
+  /// The ID of the headset's speaker, if there is one.
+  pub render_device_id: Option<String>,
+  /// The ID of the headset's microphone, if there is one.
+  pub capture_device_id: Option<String>,
+}
+
+/// A Windows GUID.
+///
+/// The `Display` format is the usual `{xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx}`
+/// form.
+#[derive(Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct Guid {
+  /// The first 8 hex digits.
+  pub data1: u32,
+  /// The next 4 hex digits.
+  pub data2: u16,
+  /// The next 4 hex digits.
+  pub data3: u16,
+  /// The last 16 hex digits.
+  pub data4: [u8; 8],
+}
+
+impl Guid {
+  /// If this is `GUID_NULL` (all zeroes).
+  pub fn is_null(&self) -> bool {
+    *self == Guid::default()
+  }
+}
+
+impl fmt::Display for Guid {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    let d = &self.data4;
+    write!(
+      f,
+      "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}}}",
+      self.data1, self.data2, self.data3, d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7]
+    )
+  }
+}
+
+#[test]
+fn guid_display_test() {
+  let guid = Guid {
+    data1: 0x6B29FC40,
+    data2: 0xCA47,
+    data3: 0x1067,
+    data4: [0xB3, 0x1D, 0x00, 0xDD, 0x01, 0x06, 0x62, 0xDA],
+  };
+  assert_eq!(guid.to_string(), "{6B29FC40-CA47-1067-B31D-00DD010662DA}");
+  assert!(!guid.is_null());
+  assert!(Guid::default().is_null());
+}
+
+/// The DirectSound device GUIDs of the headset plugged into a controller.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct XInputDSoundAudioDeviceGuids {
+  /// The GUID of the headset's speaker, if there is one.
+  pub render_guid: Option<Guid>,
+  /// The GUID of the headset's microphone, if there is one.
+  pub capture_guid: Option<Guid>,
+}
+
+/// Turns a wide string buffer and the length that XInput gave us into a
+/// `String`, with an empty string counting as no string at all.
+#[cfg(windows)]
+fn device_id_from_wide(buffer: &[u16], len: UINT) -> Option<String> {
+  let buffer = &buffer[..(len as usize).min(buffer.len())];
+  let end = buffer.iter().position(|&u| u == 0).unwrap_or(buffer.len());
+  if end == 0 {
+    None
+  } else {
+    Some(String::from_utf16_lossy(&buffer[..end]))
+  }
+}
+
+#[cfg(windows)]
+impl XInputHandle {
+  /// Get the audio device IDs of the headset plugged into a controller.
+  ///
+  /// This is only available in `xinput1_4.dll`. If there's no headset then
+  /// both IDs are `None`.
+  ///
+  /// See the [MSDN documentation for XInputGetAudioDeviceIds](https://docs.microsoft.com/en-us/windows/desktop/api/xinput/nf-xinput-xinputgetaudiodeviceids).
+  pub fn get_audio_device_ids(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputAudioDeviceIds, XInputOptionalFnUsageError> {
+    if user_index >= 4 {
+      Err(XInputOptionalFnUsageError::InvalidControllerID)
+    } else if let Some(func) = self.opt_xinput_get_audio_device_ids {
+      let mut render = [0u16; 256];
+      let mut render_count = render.len() as UINT;
+      let mut capture = [0u16; 256];
+      let mut capture_count = capture.len() as UINT;
+      let return_status = unsafe {
+        func(
+          user_index,
+          render.as_mut_ptr(),
+          &mut render_count,
+          capture.as_mut_ptr(),
+          &mut capture_count,
+        )
+      };
+      match return_status {
+        ERROR_SUCCESS => Ok(XInputAudioDeviceIds {
+          render_device_id: device_id_from_wide(&render, render_count),
+          capture_device_id: device_id_from_wide(&capture, capture_count),
+        }),
+        ERROR_DEVICE_NOT_CONNECTED => Err(XInputOptionalFnUsageError::DeviceNotConnected),
+        s => {
+          trace!("Unexpected error code: {}", s);
+          Err(XInputOptionalFnUsageError::UnknownError(s))
+        }
+      }
+    } else {
+      Err(XInputOptionalFnUsageError::FunctionNotLoaded)
+    }
+  }
+
+  /// Get the DirectSound GUIDs of the headset plugged into a controller.
+  ///
+  /// This was **removed** in `xinput1_4.dll`, so you'll only have it with the
+  /// older DLLs. Use `get_audio_device_ids` with newer DLLs. If there's no
+  /// headset then both GUIDs are `None`.
+  ///
+  /// See the [MSDN documentation for XInputGetDSoundAudioDeviceGuids](https://docs.microsoft.com/en-us/windows/desktop/api/xinput/nf-xinput-xinputgetdsoundaudiodeviceguids).
+  pub fn get_dsound_audio_device_guids(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputDSoundAudioDeviceGuids, XInputOptionalFnUsageError> {
+    if user_index >= 4 {
+      Err(XInputOptionalFnUsageError::InvalidControllerID)
+    } else if let Some(func) = self.opt_xinput_get_dsound_audio_device_guids {
+      let mut render: GUID = unsafe { ::std::mem::zeroed() };
+      let mut capture: GUID = unsafe { ::std::mem::zeroed() };
+      let return_status = unsafe { func(user_index, &mut render, &mut capture) };
+      let to_guid = |g: GUID| {
+        let guid = Guid {
+          data1: g.Data1,
+          data2: g.Data2,
+          data3: g.Data3,
+          data4: g.Data4,
+        };
+        if guid.is_null() {
+          None
+        } else {
+          Some(guid)
+        }
+      };
+      match return_status {
+        ERROR_SUCCESS => Ok(XInputDSoundAudioDeviceGuids {
+          render_guid: to_guid(render),
+          capture_guid: to_guid(capture),
+        }),
+        ERROR_DEVICE_NOT_CONNECTED => Err(XInputOptionalFnUsageError::DeviceNotConnected),
+        s => {
+          trace!("Unexpected error code: {}", s);
+          Err(XInputOptionalFnUsageError::UnknownError(s))
+        }
+      }
+    } else {
+      Err(XInputOptionalFnUsageError::FunctionNotLoaded)
+    }
+  }
+}
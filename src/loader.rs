@@ -0,0 +1,130 @@
+//! A configurable DLL search, for when `load_default` isn't what you want.
+
+use std::env;
+use std::ffi::{OsStr, OsString};
+
+use super::{XInputHandle, XInputLoadError};
+
+/// Builds up a list of XInput DLLs to try, then loads the first one that works.
+///
+/// The search goes in this order:
+///
+/// 1. The value of each environment variable added with `env_var`, if it's set
+///    and not empty. This lets testers and modders point the game at some other
+///    DLL without a rebuild.
+/// 2. Each DLL added with `dll` or `dll_beside_executable`, in the order they
+///    were added.
+/// 3. If `fallback_to_defaults` is on (the default), the usual
+///    `XInputHandle::DEFAULT_DLL_NAMES`.
+///
+/// A DLL can be given as a plain name, which goes through the normal Windows
+/// DLL search order, or as a full path, which loads exactly that file and
+/// nothing else.
+///
+/// ```no_run
+/// # use rusty_xinput::XInputLoader;
+/// let handle = XInputLoader::new()
+///   .env_var("MY_GAME_XINPUT_DLL")
+///   .dll_beside_executable("xinput_wrapper.dll")
+///   .fallback_to_defaults(false)
+///   .load();
+/// ```
+#[derive(Debug, Clone)]
+pub struct XInputLoader {
+  env_vars: Vec<OsString>,
+  dlls: Vec<OsString>,
+  fallback_to_defaults: bool,
+}
+
+impl Default for XInputLoader {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl XInputLoader {
+  /// A loader with an empty search list that falls back to the defaults.
+  pub fn new() -> Self {
+    XInputLoader {
+      env_vars: Vec::new(),
+      dlls: Vec::new(),
+      fallback_to_defaults: true,
+    }
+  }
+
+  /// Adds an environment variable that can name a DLL to try first.
+  pub fn env_var<S: AsRef<OsStr>>(mut self, name: S) -> Self {
+    self.env_vars.push(name.as_ref().to_os_string());
+    self
+  }
+
+  /// Adds a DLL name or path to the search list.
+  pub fn dll<S: AsRef<OsStr>>(mut self, name_or_path: S) -> Self {
+    self.dlls.push(name_or_path.as_ref().to_os_string());
+    self
+  }
+
+  /// Adds a DLL in the same directory as the running executable.
+  ///
+  /// If the executable's location can't be determined this entry is skipped.
+  pub fn dll_beside_executable<S: AsRef<OsStr>>(self, file_name: S) -> Self {
+    match env::current_exe() {
+      Ok(exe) => match exe.parent() {
+        Some(dir) => {
+          let path = dir.join(file_name.as_ref());
+          self.dll(path)
+        }
+        None => self,
+      },
+      Err(e) => {
+        debug!("Could not find the executable's directory: {}", e);
+        self
+      }
+    }
+  }
+
+  /// Sets if the default DLL names are searched after everything else.
+  pub fn fallback_to_defaults(mut self, fallback: bool) -> Self {
+    self.fallback_to_defaults = fallback;
+    self
+  }
+
+  /// Every DLL that `load` would try, in order.
+  ///
+  /// Environment variables are read at the time of this call.
+  pub fn search_list(&self) -> Vec<OsString> {
+    let mut list = Vec::new();
+    for var in self.env_vars.iter() {
+      match env::var_os(var) {
+        Some(ref value) if !value.is_empty() => list.push(value.clone()),
+        _ => trace!("Environment variable {:?} isn't set.", var),
+      }
+    }
+    list.extend(self.dlls.iter().cloned());
+    if self.fallback_to_defaults {
+      list.extend(XInputHandle::DEFAULT_DLL_NAMES.iter().map(OsString::from));
+    }
+    list
+  }
+
+  /// Tries each DLL of the search list in turn, stopping at the first one that
+  /// loads.
+  ///
+  /// # Failure
+  ///
+  /// If nothing could be loaded, the error lists each DLL that was tried. If
+  /// the search list was empty the list of attempts is empty too.
+  pub fn load(&self) -> Result<XInputHandle, XInputLoadError> {
+    let mut attempts = Vec::new();
+    for lib_name in self.search_list() {
+      match XInputHandle::load_attempt(&lib_name) {
+        Ok(handle) => return Ok(handle),
+        Err(attempt) => attempts.push(attempt),
+      }
+    }
+
+    let err = XInputLoadError { attempts };
+    debug!("Failure: {}", err);
+    Err(err)
+  }
+}
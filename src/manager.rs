@@ -0,0 +1,279 @@
+//! Keeping track of all four controller slots without stalling on the empty
+//! ones.
+
+use std::collections::vec_deque::{Drain, VecDeque};
+use std::fmt::{self, Debug, Formatter};
+
+use super::{ControllerBackend, XInputCapabilities, XInputState, XInputUsageError};
+#[cfg(windows)]
+use super::{XInputHandle, XInputLoadError};
+
+/// A change in which controllers are plugged in, found by a
+/// `ControllerManager`.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum ControllerEvent {
+  /// A controller was found in a slot for the first time.
+  Connected {
+    /// The slot.
+    slot: u32,
+    /// What the controller can do, or `None` if asking for that failed.
+    capabilities: Option<XInputCapabilities>,
+  },
+  /// A connected controller went missing.
+  Disconnected {
+    /// The slot.
+    slot: u32,
+  },
+  /// A slot that had a controller before has one again. It might not be the
+  /// same controller, so check the capabilities again if that matters.
+  Reconnected {
+    /// The slot.
+    slot: u32,
+  },
+}
+
+/// Polls all four controller slots, following the advice on
+/// `XInputHandle::get_state`.
+///
+/// Each `update` polls every connected slot, and then polls at most
+/// `disconnected_budget` of the disconnected slots, taking turns between them
+/// from one update to the next. That way a missing controller only costs you
+/// the polling stall once every few updates instead of every time, and a
+/// controller that gets plugged in is still noticed within a few updates.
+///
+/// Every slot starts out as disconnected, so with the default budget of 1 it
+/// takes four updates to find all the controllers. Call `poll_all` when you
+/// want to check every slot right away, eg: when starting up, or when you get
+/// a plug and play event from the OS.
+///
+/// A `DeviceNotConnected` error marks a slot as disconnected. Other errors
+/// don't tell us anything about the controller, so they leave the slot as it
+/// was.
+///
+/// Whenever a slot changes between connected and disconnected a
+/// `ControllerEvent` is queued up. Use `drain_events` after each update to
+/// handle them, or the queue just keeps growing.
+pub struct ControllerManager<B> {
+  backend: B,
+  states: [Option<XInputState>; 4],
+  ever_connected: [bool; 4],
+  events: VecDeque<ControllerEvent>,
+  disconnected_budget: usize,
+  next_disconnected: usize,
+}
+
+impl<B> Debug for ControllerManager<B> {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    let connected = |i: usize| self.states[i].is_some();
+    write!(
+      f,
+      "ControllerManager(connected = [{}, {}, {}, {}])",
+      connected(0),
+      connected(1),
+      connected(2),
+      connected(3)
+    )
+  }
+}
+
+#[cfg(windows)]
+impl ControllerManager<XInputHandle> {
+  /// Loads XInput with `XInputHandle::load_default_detailed` and makes a
+  /// manager for it.
+  pub fn load_default() -> Result<Self, XInputLoadError> {
+    XInputHandle::load_default_detailed().map(ControllerManager::new)
+  }
+}
+
+impl<B: ControllerBackend> ControllerManager<B> {
+  /// Makes a manager with every slot disconnected and a disconnected budget of
+  /// 1.
+  pub fn new(backend: B) -> Self {
+    ControllerManager {
+      backend,
+      states: [None; 4],
+      ever_connected: [false; 4],
+      events: VecDeque::new(),
+      disconnected_budget: 1,
+      next_disconnected: 0,
+    }
+  }
+
+  /// Sets how many disconnected slots can be polled in each `update`. A budget
+  /// of 0 means disconnected slots are only polled by `poll_all`.
+  pub fn with_disconnected_budget(mut self, budget: usize) -> Self {
+    self.disconnected_budget = budget;
+    self
+  }
+
+  /// How many disconnected slots can be polled in each `update`.
+  pub fn disconnected_budget(&self) -> usize {
+    self.disconnected_budget
+  }
+
+  /// The backend being polled.
+  pub fn backend(&self) -> &B {
+    &self.backend
+  }
+
+  /// Gives back the backend.
+  pub fn into_backend(self) -> B {
+    self.backend
+  }
+
+  /// The latest state of a slot, or `None` if it's disconnected (or isn't a
+  /// slot at all).
+  pub fn state(&self, user_index: u32) -> Option<XInputState> {
+    self
+      .states
+      .get(user_index as usize)
+      .and_then(|&state| state)
+  }
+
+  /// If a slot is connected, as of the last time it was polled.
+  pub fn is_connected(&self, user_index: u32) -> bool {
+    self.state(user_index).is_some()
+  }
+
+  /// The slots that are connected, as of the last time they were polled.
+  pub fn connected_slots(&self) -> Vec<u32> {
+    (0..4).filter(|&i| self.is_connected(i)).collect()
+  }
+
+  /// Polls every connected slot, and up to `disconnected_budget` of the
+  /// disconnected ones.
+  pub fn update(&mut self) {
+    let was_disconnected = self.states.map(|state| state.is_none());
+    for (i, &missing) in was_disconnected.iter().enumerate() {
+      if !missing {
+        self.poll(i);
+      }
+    }
+    // A slot that just went missing was already polled this update, so only
+    // the slots that were missing to begin with take turns.
+    let mut budget = self.disconnected_budget;
+    for offset in 0..4 {
+      if budget == 0 {
+        break;
+      }
+      let i = (self.next_disconnected + offset) % 4;
+      if was_disconnected[i] {
+        self.poll(i);
+        budget -= 1;
+        self.next_disconnected = (i + 1) % 4;
+      }
+    }
+  }
+
+  /// Polls all four slots, even the disconnected ones.
+  pub fn poll_all(&mut self) {
+    for i in 0..4 {
+      self.poll(i);
+    }
+  }
+
+  /// Takes all the events queued up so far, oldest first.
+  pub fn drain_events(&mut self) -> Drain<'_, ControllerEvent> {
+    self.events.drain(..)
+  }
+
+  fn poll(&mut self, i: usize) {
+    let slot = i as u32;
+    match self.backend.get_state(slot) {
+      Ok(state) => {
+        if self.states[i].is_none() {
+          let event = if self.ever_connected[i] {
+            ControllerEvent::Reconnected { slot }
+          } else {
+            ControllerEvent::Connected {
+              slot,
+              capabilities: self.backend.get_capabilities(slot).ok(),
+            }
+          };
+          self.events.push_back(event);
+          self.ever_connected[i] = true;
+        }
+        self.states[i] = Some(state);
+      }
+      Err(XInputUsageError::DeviceNotConnected) => {
+        if self.states[i].take().is_some() {
+          self
+            .events
+            .push_back(ControllerEvent::Disconnected { slot });
+        }
+      }
+      Err(_) => (),
+    }
+  }
+}
+
+#[test]
+fn controller_manager_test() {
+  use super::{MockBackend, XINPUT_GAMEPAD_A};
+
+  let mock = MockBackend::new();
+  mock.connect(2);
+  let mut manager = ControllerManager::new(&mock);
+  assert_eq!(manager.connected_slots(), Vec::<u32>::new());
+  // one missing slot per update: 0, 1, then 2 is found
+  manager.update();
+  manager.update();
+  assert!(!manager.is_connected(2));
+  manager.update();
+  assert_eq!(manager.connected_slots(), vec![2]);
+  let capabilities = mock.get_capabilities(2).ok();
+  assert_eq!(
+    manager.drain_events().collect::<Vec<_>>(),
+    vec![ControllerEvent::Connected {
+      slot: 2,
+      capabilities,
+    }]
+  );
+
+  // connected slots are polled every update
+  mock.press_buttons(2, XINPUT_GAMEPAD_A);
+  manager.update();
+  assert_eq!(
+    manager.state(2).unwrap().raw.Gamepad.wButtons,
+    XINPUT_GAMEPAD_A
+  );
+
+  // that update took the turn for 3, so next is 0, then 1
+  mock.connect(1);
+  manager.update();
+  assert!(!manager.is_connected(1));
+  manager.update();
+  assert_eq!(manager.connected_slots(), vec![1, 2]);
+
+  mock.disconnect(2);
+  manager.update();
+  assert!(manager.state(2).is_none());
+  assert!(manager.state(9).is_none());
+  // unplugged and plugged back in before it was polled again: no events
+  mock.disconnect(1);
+  mock.connect(1);
+  manager.update();
+  mock.connect(2);
+  for _ in 0..4 {
+    manager.update();
+  }
+  assert_eq!(
+    manager.drain_events().collect::<Vec<_>>(),
+    vec![
+      ControllerEvent::Connected {
+        slot: 1,
+        capabilities,
+      },
+      ControllerEvent::Disconnected { slot: 2 },
+      ControllerEvent::Reconnected { slot: 2 },
+    ]
+  );
+  assert_eq!(manager.drain_events().count(), 0);
+
+  // with no budget only `poll_all` finds new controllers
+  let mut manager = ControllerManager::new(&mock).with_disconnected_budget(0);
+  manager.update();
+  assert!(manager.connected_slots().is_empty());
+  manager.poll_all();
+  assert_eq!(manager.connected_slots(), vec![1, 2]);
+}
@@ -0,0 +1,451 @@
+//! Recognizing fighting game motion inputs, like quarter circles and charges.
+
+use std::collections::VecDeque;
+
+use super::{Button, Buttons, Direction8, DirectionQuantizer, XInputState};
+
+/// Which way the player is facing, so that "forward" can be worked out.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum Facing {
+  /// Forward is to the right. This is how motions are written down.
+  Right,
+  /// Forward is to the left, so every direction is mirrored.
+  Left,
+}
+
+impl Default for Facing {
+  /// `Right`
+  fn default() -> Self {
+    Facing::Right
+  }
+}
+
+impl Facing {
+  /// Turns an on screen direction into one where forward is to the right.
+  pub fn relative(self, d: Direction8) -> Direction8 {
+    match self {
+      Facing::Right => d,
+      Facing::Left => d.mirrored(),
+    }
+  }
+}
+
+/// One step of a motion: any of a set of numpad directions, held for at least
+/// some number of frames.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct MotionStep {
+  directions: u16,
+  hold: u32,
+}
+
+impl MotionStep {
+  /// A step that accepts any of the numpad digits given, for at least one
+  /// frame. Digits outside of 1 to 9 are ignored.
+  pub fn new(digits: &[u8]) -> Self {
+    let directions = digits
+      .iter()
+      .filter(|&&digit| (1..=9).contains(&digit))
+      .fold(0, |mask, &digit| mask | 1 << digit);
+    MotionStep {
+      directions,
+      hold: 1,
+    }
+  }
+
+  /// A step that accepts any of the numpad digits given, and has to be held
+  /// for at least `frames` frames in a row.
+  pub fn charge(digits: &[u8], frames: u32) -> Self {
+    MotionStep {
+      hold: frames.max(1),
+      ..MotionStep::new(digits)
+    }
+  }
+
+  /// If this step accepts a direction (already made relative to the facing).
+  pub fn accepts(&self, d: Direction8) -> bool {
+    self.directions & 1 << d.numpad() != 0
+  }
+
+  /// How many frames in a row this step has to be held for.
+  pub fn hold_frames(&self) -> u32 {
+    self.hold
+  }
+}
+
+/// The directions that make up a `MotionPattern`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum MotionShape {
+  /// Steps that have to happen in order. An empty sequence matches any press
+  /// of the pattern's buttons.
+  Sequence(Vec<MotionStep>),
+  /// A full circle (a "360"): up, down, left, and right all have to be
+  /// pressed within the window, in any order. A diagonal counts for both of
+  /// its directions.
+  FullCircle,
+}
+
+/// A motion to watch for, and the buttons that finish it.
+///
+/// The timing is all in frames (calls to `MotionParser::update`):
+///
+/// * `window`: Every step has to have been pressed within this many frames of
+///   the button press. The hold of a charge step can start before the window,
+///   but it has to still be held within it.
+/// * `leniency`: The most frames of other directions allowed between one
+///   step and the next, and between the last step and the button press. The
+///   last direction can also be pressed on the same frame as the button.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct MotionPattern {
+  /// The directions.
+  pub shape: MotionShape,
+  /// Pressing any of these buttons finishes the motion.
+  pub buttons: Buttons,
+  /// How many frames the whole motion can take.
+  pub window: u32,
+  /// How many stray frames are allowed between steps.
+  pub leniency: u32,
+}
+
+impl MotionPattern {
+  /// A pattern with a window of 20 frames and a leniency of 5 frames.
+  pub fn new(shape: MotionShape, buttons: Buttons) -> Self {
+    MotionPattern {
+      shape,
+      buttons,
+      window: 20,
+      leniency: 5,
+    }
+  }
+
+  /// A sequence of single numpad directions, eg: `&[2, 3, 6]`.
+  pub fn sequence(digits: &[u8], buttons: Buttons) -> Self {
+    let steps = digits
+      .iter()
+      .map(|&digit| MotionStep::new(&[digit]))
+      .collect();
+    MotionPattern::new(MotionShape::Sequence(steps), buttons)
+  }
+
+  /// Quarter circle forward, 236.
+  pub fn quarter_circle_forward(buttons: Buttons) -> Self {
+    MotionPattern::sequence(&[2, 3, 6], buttons)
+  }
+
+  /// Quarter circle back, 214.
+  pub fn quarter_circle_back(buttons: Buttons) -> Self {
+    MotionPattern::sequence(&[2, 1, 4], buttons)
+  }
+
+  /// Dragon punch, 623.
+  pub fn dragon_punch(buttons: Buttons) -> Self {
+    MotionPattern::sequence(&[6, 2, 3], buttons)
+  }
+
+  /// Hold any back direction (1, 4, or 7) for `frames`, then press any
+  /// forward direction (3, 6, or 9).
+  pub fn charge_back_forward(frames: u32, buttons: Buttons) -> Self {
+    let steps = vec![MotionStep::charge(&[1, 4, 7], frames), MotionStep::new(&[3, 6, 9])];
+    MotionPattern::new(MotionShape::Sequence(steps), buttons)
+  }
+
+  /// Hold any down direction (1, 2, or 3) for `frames`, then press any up
+  /// direction (7, 8, or 9).
+  pub fn charge_down_up(frames: u32, buttons: Buttons) -> Self {
+    let steps = vec![MotionStep::charge(&[1, 2, 3], frames), MotionStep::new(&[7, 8, 9])];
+    MotionPattern::new(MotionShape::Sequence(steps), buttons)
+  }
+
+  /// A full circle, with a window of 30 frames.
+  pub fn full_circle(buttons: Buttons) -> Self {
+    MotionPattern::new(MotionShape::FullCircle, buttons).with_window(30)
+  }
+
+  /// Sets how many frames the whole motion can take.
+  pub fn with_window(mut self, frames: u32) -> Self {
+    self.window = frames;
+    self
+  }
+
+  /// Sets how many stray frames are allowed between steps.
+  pub fn with_leniency(mut self, frames: u32) -> Self {
+    self.leniency = frames;
+    self
+  }
+
+  /// How many frames of history are needed to check this pattern.
+  fn history_needed(&self) -> usize {
+    let leniency = self.leniency as usize;
+    match self.shape {
+      MotionShape::Sequence(ref steps) => {
+        let holds: usize = steps.iter().map(|step| step.hold as usize).sum();
+        self.window as usize + holds + leniency * (steps.len() + 1) + 1
+      }
+      MotionShape::FullCircle => self.window as usize + leniency + 1,
+    }
+  }
+
+  /// Checks the history (newest frame first) for this pattern's directions.
+  fn matches(&self, history: &VecDeque<Direction8>, facing: Facing) -> bool {
+    let window = self.window as usize;
+    let leniency = self.leniency as usize;
+    let at = |frame: usize| facing.relative(history[frame]);
+    match self.shape {
+      MotionShape::Sequence(ref steps) => {
+        // Work backwards from the button press, finding each step in turn.
+        let mut from = 0;
+        for step in steps.iter().rev() {
+          let newest = (from..=from + leniency)
+            .take_while(|&frame| frame < history.len())
+            .find(|&frame| step.accepts(at(frame)));
+          let newest = match newest {
+            Some(frame) if frame < window => frame,
+            _ => return false,
+          };
+          let mut oldest = newest;
+          while oldest + 1 < history.len() && step.accepts(at(oldest + 1)) {
+            oldest += 1;
+          }
+          if oldest - newest + 1 < step.hold as usize {
+            return false;
+          }
+          from = oldest + 1;
+        }
+        true
+      }
+      MotionShape::FullCircle => {
+        let recent = history.len().min(leniency + 1);
+        if history
+          .iter()
+          .take(recent)
+          .all(|&d| d == Direction8::Neutral)
+        {
+          return false;
+        }
+        let (mut up, mut down, mut left, mut right) = (false, false, false, false);
+        for &d in history.iter().take(window) {
+          let (x, y) = d.vector();
+          up |= y > 0;
+          down |= y < 0;
+          left |= x < 0;
+          right |= x > 0;
+        }
+        up && down && left && right
+      }
+    }
+  }
+}
+
+/// A motion that was completed, found by a `MotionParser`.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct MotionMatch {
+  /// The index of the pattern in `MotionParser::patterns`.
+  pub pattern: usize,
+  /// The button that finished the motion.
+  pub button: Button,
+}
+
+/// Turns the D-pad and left stick over time into fighting game motions.
+///
+/// Call `update` once per game frame with the newest state. Each frame the
+/// D-pad and left stick are combined into one `Direction8` (the D-pad wins if
+/// it's pressed), and that's added to the history. When a button is pressed
+/// the patterns are checked in the order they were added, and the first one
+/// that matches is returned, so add the patterns that should win ties first
+/// (eg: a dragon punch before a quarter circle forward).
+///
+/// The history is kept as on screen directions, and it's made relative to the
+/// facing when the patterns are checked. That way a motion still comes out
+/// if the facing changes part way through it, the same as in most fighting
+/// games.
+#[derive(Debug, Clone)]
+pub struct MotionParser {
+  patterns: Vec<MotionPattern>,
+  facing: Facing,
+  quantizer: DirectionQuantizer,
+  history: VecDeque<Direction8>,
+  history_len: usize,
+  buttons: Buttons,
+}
+
+impl Default for MotionParser {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl MotionParser {
+  /// A parser with no patterns, facing right, using a default
+  /// `DirectionQuantizer` for the stick.
+  pub fn new() -> Self {
+    MotionParser {
+      patterns: Vec::new(),
+      facing: Facing::Right,
+      quantizer: DirectionQuantizer::new(),
+      history: VecDeque::new(),
+      history_len: 1,
+      buttons: Buttons::NONE,
+    }
+  }
+
+  /// Sets the quantizer used to turn the left stick into a direction.
+  pub fn with_quantizer(mut self, quantizer: DirectionQuantizer) -> Self {
+    self.quantizer = quantizer;
+    self
+  }
+
+  /// Adds a pattern to watch for.
+  pub fn with_pattern(mut self, pattern: MotionPattern) -> Self {
+    self.add_pattern(pattern);
+    self
+  }
+
+  /// Adds a pattern to watch for, giving back its index.
+  pub fn add_pattern(&mut self, pattern: MotionPattern) -> usize {
+    self.history_len = self.history_len.max(pattern.history_needed());
+    self.patterns.push(pattern);
+    self.patterns.len() - 1
+  }
+
+  /// The patterns being watched for.
+  pub fn patterns(&self) -> &[MotionPattern] {
+    &self.patterns
+  }
+
+  /// Which way the player is facing.
+  pub fn facing(&self) -> Facing {
+    self.facing
+  }
+
+  /// Sets which way the player is facing.
+  pub fn set_facing(&mut self, facing: Facing) {
+    self.facing = facing;
+  }
+
+  /// The directions of the kept history as numpad digits relative to the
+  /// current facing, oldest first.
+  pub fn numpad_history(&self) -> Vec<u8> {
+    self
+      .history
+      .iter()
+      .rev()
+      .map(|&d| self.facing.relative(d).numpad())
+      .collect()
+  }
+
+  /// Forgets the history and buttons, eg: when the controller is
+  /// disconnected. The patterns and facing are kept.
+  pub fn reset(&mut self) {
+    self.quantizer.reset();
+    self.history.clear();
+    self.buttons = Buttons::NONE;
+  }
+
+  /// Feeds in the state for a new frame, giving back the motion that it
+  /// completed, if any.
+  pub fn update(&mut self, state: &XInputState) -> Option<MotionMatch> {
+    let direction = self
+      .quantizer
+      .update8_with_dpad(state.left_stick_normalized(), state.dpad_direction());
+    self.update_with(direction, state.buttons())
+  }
+
+  /// Like `update`, but with the direction and buttons for the frame given
+  /// directly. The direction is on screen, not relative to the facing.
+  pub fn update_with(&mut self, direction: Direction8, buttons: Buttons) -> Option<MotionMatch> {
+    self.history.push_front(direction);
+    self.history.truncate(self.history_len);
+    let pressed = buttons - self.buttons;
+    self.buttons = buttons;
+    if pressed.is_empty() {
+      return None;
+    }
+    for (index, pattern) in self.patterns.iter().enumerate() {
+      if let Some(button) = (pressed & pattern.buttons).iter().next() {
+        if pattern.matches(&self.history, self.facing) {
+          return Some(MotionMatch {
+            pattern: index,
+            button,
+          });
+        }
+      }
+    }
+    None
+  }
+}
+
+#[test]
+fn motion_parser_test() {
+  let punch = Buttons::from(Button::West);
+  let kick = Buttons::from(Button::South);
+  let mut parser = MotionParser::new()
+    .with_pattern(MotionPattern::dragon_punch(punch))
+    .with_pattern(MotionPattern::quarter_circle_forward(punch))
+    .with_pattern(MotionPattern::charge_back_forward(40, kick))
+    .with_pattern(MotionPattern::full_circle(kick));
+  let run = |parser: &mut MotionParser, digits: &[u8], buttons: Buttons| {
+    parser.reset();
+    let mut found = None;
+    for &digit in digits {
+      let d = Direction8::from_numpad(digit).unwrap();
+      found = parser.update_with(d, Buttons::NONE).or(found);
+    }
+    found.or_else(|| parser.update_with(Direction8::Neutral, buttons))
+  };
+  let matched = |pattern: usize, button: Buttons| {
+    Some(MotionMatch {
+      pattern,
+      button: button.iter().next().unwrap(),
+    })
+  };
+
+  assert_eq!(run(&mut parser, &[2, 3, 6], punch), matched(1, punch));
+  assert_eq!(run(&mut parser, &[6, 2, 3], punch), matched(0, punch));
+  // the dragon punch wins over the quarter circle inside it
+  assert_eq!(run(&mut parser, &[2, 3, 6, 2, 3], punch), matched(0, punch));
+  // a few stray frames are fine, too many aren't
+  assert_eq!(run(&mut parser, &[2, 5, 5, 3, 6], punch), matched(1, punch));
+  assert_eq!(run(&mut parser, &[2, 3, 6, 5, 5, 5, 5, 5, 5], punch), None);
+  // the whole motion has to fit in the window
+  let mut slow = vec![2];
+  slow.extend_from_slice(&[3; 20]);
+  slow.push(6);
+  assert_eq!(run(&mut parser, &slow, punch), None);
+  // the wrong button doesn't do it
+  assert_eq!(run(&mut parser, &[2, 3, 6], kick), None);
+
+  // facing left mirrors everything
+  parser.set_facing(Facing::Left);
+  assert_eq!(run(&mut parser, &[2, 3, 6], punch), None);
+  assert_eq!(run(&mut parser, &[2, 1, 4], punch), matched(1, punch));
+  assert_eq!(parser.numpad_history().last(), Some(&5));
+  parser.set_facing(Facing::Right);
+
+  // charges have to be held long enough
+  let mut charge = vec![4; 45];
+  charge.push(6);
+  assert_eq!(run(&mut parser, &charge, kick), matched(2, kick));
+  let mut short_charge = vec![1; 20];
+  short_charge.push(6);
+  assert_eq!(run(&mut parser, &short_charge, kick), None);
+
+  assert_eq!(
+    run(&mut parser, &[6, 3, 2, 1, 4, 7, 8], kick),
+    matched(3, kick)
+  );
+  assert_eq!(run(&mut parser, &[6, 3, 2, 1, 4], kick), None);
+
+  // the same thing from a real state, with the stick and then the D-pad
+  use super::{XINPUT_GAMEPAD_DPAD_RIGHT, XINPUT_GAMEPAD_X};
+  parser.reset();
+  let mut state = XInputState::default();
+  state.raw.Gamepad.sThumbLY = -i16::MAX;
+  assert_eq!(parser.update(&state), None);
+  state.raw.Gamepad.sThumbLX = i16::MAX;
+  assert_eq!(parser.update(&state), None);
+  state.raw.Gamepad.sThumbLX = 0;
+  state.raw.Gamepad.sThumbLY = 0;
+  state.raw.Gamepad.wButtons = XINPUT_GAMEPAD_DPAD_RIGHT;
+  assert_eq!(parser.update(&state), None);
+  state.raw.Gamepad.wButtons = XINPUT_GAMEPAD_X;
+  assert_eq!(parser.update(&state), matched(1, punch));
+}
@@ -0,0 +1,221 @@
+//! Polling the controllers on a thread of their own.
+
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryIter};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::{ControllerBackend, ControllerEvent, ControllerManager, XInputState};
+
+/// What happened in a `PollingEvent`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PollingEventKind {
+  /// A controller was plugged in or unplugged.
+  Connection(ControllerEvent),
+  /// A connected controller has a new state. This is sent when the packet
+  /// number changes, and also right after a controller connects.
+  State {
+    /// The slot.
+    slot: u32,
+    /// The new state.
+    state: XInputState,
+  },
+}
+
+/// The shortest interval a `PollingThread` will use. Controllers don't report
+/// any faster than this, so a shorter interval (or none at all) would only
+/// keep a core busy.
+pub const MIN_POLLING_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Something the polling thread saw, and when it saw it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PollingEvent {
+  /// When the poll that found this finished.
+  pub at: Instant,
+  /// What happened.
+  pub kind: PollingEventKind,
+}
+
+/// Runs a `ControllerManager` on a background thread, sending what it sees
+/// back over a channel.
+///
+/// The thread calls `update` on the manager once every `interval` (eg: 4ms
+/// for 250 Hz, or 1ms for 1 kHz), so any polling stalls from disconnected
+/// slots happen on that thread instead of yours. Each event has the `Instant`
+/// of the poll that found it, which is usually much more precise than the
+/// time of the frame where you handle it. If an update runs long, the next
+/// one starts right away instead of trying to make up for the missed ticks.
+/// An interval shorter than `MIN_POLLING_INTERVAL` is raised to that.
+///
+/// How closely the interval is kept depends on the OS. On Windows a sleep
+/// normally lasts at least one system timer tick (often ~15ms) unless the
+/// timer resolution has been raised (eg: with `timeBeginPeriod`).
+///
+/// The thread stops when this is dropped, or when `stop` is called.
+pub struct PollingThread<B> {
+  receiver: Receiver<PollingEvent>,
+  stop: Arc<AtomicBool>,
+  interval: Duration,
+  handle: Option<JoinHandle<ControllerManager<B>>>,
+}
+
+impl<B> Debug for PollingThread<B> {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    write!(f, "PollingThread(running = {})", self.handle.is_some())
+  }
+}
+
+impl<B: ControllerBackend + Send + 'static> PollingThread<B> {
+  /// Starts a thread polling with the manager given.
+  ///
+  /// ## Failure
+  ///
+  /// If the OS can't make the thread you get its error.
+  pub fn spawn(manager: ControllerManager<B>, interval: Duration) -> io::Result<Self> {
+    let interval = interval.max(MIN_POLLING_INTERVAL);
+    let (sender, receiver) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+    let handle = thread::Builder::new()
+      .name("rusty-xinput polling".to_string())
+      .spawn(move || poll_loop(manager, interval, &sender, &thread_stop))?;
+    Ok(PollingThread {
+      receiver,
+      stop,
+      interval,
+      handle: Some(handle),
+    })
+  }
+}
+
+impl<B> PollingThread<B> {
+  /// The interval the thread polls at, after raising it to
+  /// `MIN_POLLING_INTERVAL` if it was shorter.
+  pub fn interval(&self) -> Duration {
+    self.interval
+  }
+
+  /// The receiving end of the event channel, if you want to block on it.
+  pub fn receiver(&self) -> &Receiver<PollingEvent> {
+    &self.receiver
+  }
+
+  /// All the events that have arrived so far, without blocking.
+  pub fn try_iter(&self) -> TryIter<'_, PollingEvent> {
+    self.receiver.try_iter()
+  }
+
+  /// Stops the thread and gives back the manager, or `None` if the thread
+  /// panicked. Events that were sent but not received are lost.
+  pub fn stop(mut self) -> Option<ControllerManager<B>> {
+    self.join()
+  }
+
+  fn join(&mut self) -> Option<ControllerManager<B>> {
+    self.stop.store(true, Ordering::Relaxed);
+    self.handle.take().and_then(|handle| handle.join().ok())
+  }
+}
+
+impl<B> Drop for PollingThread<B> {
+  fn drop(&mut self) {
+    self.join();
+  }
+}
+
+fn poll_loop<B: ControllerBackend>(
+  mut manager: ControllerManager<B>,
+  interval: Duration,
+  sender: &Sender<PollingEvent>,
+  stop: &AtomicBool,
+) -> ControllerManager<B> {
+  let mut packets: [Option<u32>; 4] = [None; 4];
+  let mut next_tick = Instant::now();
+  while !stop.load(Ordering::Relaxed) {
+    manager.update();
+    let at = Instant::now();
+    let mut events: Vec<PollingEventKind> = manager
+      .drain_events()
+      .map(PollingEventKind::Connection)
+      .collect();
+    for (i, packet) in packets.iter_mut().enumerate() {
+      let slot = i as u32;
+      let state = manager.state(slot);
+      let new_packet = state.map(|state| state.raw.dwPacketNumber);
+      if let Some(state) = state {
+        if *packet != new_packet {
+          events.push(PollingEventKind::State { slot, state });
+        }
+      }
+      *packet = new_packet;
+    }
+    for kind in events {
+      // the receiver lives as long as the `PollingThread`, so this can't fail
+      // before we're told to stop anyway.
+      let _ = sender.send(PollingEvent { at, kind });
+    }
+
+    next_tick += interval;
+    let now = Instant::now();
+    if next_tick > now {
+      thread::sleep(next_tick - now);
+    } else {
+      next_tick = now;
+    }
+  }
+  manager
+}
+
+#[test]
+fn polling_thread_test() {
+  use super::{MockBackend, XINPUT_GAMEPAD_A};
+
+  let mock = Arc::new(MockBackend::new());
+  mock.connect(3);
+  let manager = ControllerManager::new(Arc::clone(&mock)).with_disconnected_budget(4);
+  let polling = PollingThread::spawn(manager, Duration::from_millis(1)).unwrap();
+  let next = || {
+    polling
+      .receiver()
+      .recv_timeout(Duration::from_secs(5))
+      .unwrap()
+  };
+
+  let connected = next();
+  match connected.kind {
+    PollingEventKind::Connection(ControllerEvent::Connected { slot: 3, .. }) => (),
+    other => panic!("{:?}", other),
+  }
+  let first = next();
+  assert_eq!(
+    first.kind,
+    PollingEventKind::State {
+      slot: 3,
+      state: mock.get_state(3).unwrap(),
+    }
+  );
+  assert_eq!(first.at, connected.at);
+
+  mock.press_buttons(3, XINPUT_GAMEPAD_A);
+  match next().kind {
+    PollingEventKind::State { slot: 3, state } => assert!(state.south_button()),
+    other => panic!("{:?}", other),
+  }
+  mock.disconnect(3);
+  let disconnected = next();
+  assert_eq!(
+    disconnected.kind,
+    PollingEventKind::Connection(ControllerEvent::Disconnected { slot: 3 })
+  );
+
+  let manager = polling.stop().unwrap();
+  assert!(manager.connected_slots().is_empty());
+
+  // no interval at all would be a busy loop
+  let polling = PollingThread::spawn(manager, Duration::from_secs(0)).unwrap();
+  assert_eq!(polling.interval(), MIN_POLLING_INTERVAL);
+  assert!(polling.stop().is_some());
+}
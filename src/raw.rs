@@ -0,0 +1,270 @@
+//! Our own copies of the XInput C types and constants.
+//!
+//! These match the layout of the types in `xinput.h` exactly, so they can be
+//! passed straight to the DLL functions on Windows, but since they're defined
+//! here they're also available on every other platform.
+
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+/// The only device type that XInput currently reports.
+pub const XINPUT_DEVTYPE_GAMEPAD: u8 = 0x01;
+
+/// Device sub-type: the sub-type isn't known.
+pub const XINPUT_DEVSUBTYPE_UNKNOWN: u8 = 0x00;
+/// Device sub-type: gamepad.
+pub const XINPUT_DEVSUBTYPE_GAMEPAD: u8 = 0x01;
+/// Device sub-type: racing wheel.
+pub const XINPUT_DEVSUBTYPE_WHEEL: u8 = 0x02;
+/// Device sub-type: arcade stick.
+pub const XINPUT_DEVSUBTYPE_ARCADE_STICK: u8 = 0x03;
+/// Device sub-type: flight stick.
+pub const XINPUT_DEVSUBTYPE_FLIGHT_STICK: u8 = 0x04;
+/// Device sub-type: dance pad.
+pub const XINPUT_DEVSUBTYPE_DANCE_PAD: u8 = 0x05;
+/// Device sub-type: guitar.
+pub const XINPUT_DEVSUBTYPE_GUITAR: u8 = 0x06;
+/// Device sub-type: alternate guitar.
+pub const XINPUT_DEVSUBTYPE_GUITAR_ALTERNATE: u8 = 0x07;
+/// Device sub-type: drum kit.
+pub const XINPUT_DEVSUBTYPE_DRUM_KIT: u8 = 0x08;
+/// Device sub-type: bass guitar.
+pub const XINPUT_DEVSUBTYPE_GUITAR_BASS: u8 = 0x0B;
+/// Device sub-type: arcade pad.
+pub const XINPUT_DEVSUBTYPE_ARCADE_PAD: u8 = 0x13;
+
+/// Capability flag: force feedback is supported.
+pub const XINPUT_CAPS_FFB_SUPPORTED: u16 = 0x0001;
+/// Capability flag: the device is wireless.
+pub const XINPUT_CAPS_WIRELESS: u16 = 0x0002;
+/// Capability flag: the device has an integrated voice device.
+pub const XINPUT_CAPS_VOICE_SUPPORTED: u16 = 0x0004;
+/// Capability flag: plug-in modules (eg: a text input device) are supported.
+pub const XINPUT_CAPS_PMD_SUPPORTED: u16 = 0x0008;
+/// Capability flag: the device lacks menu navigation buttons.
+pub const XINPUT_CAPS_NO_NAVIGATION: u16 = 0x0010;
+
+/// `wButtons` bit: D-pad up.
+pub const XINPUT_GAMEPAD_DPAD_UP: u16 = 0x0001;
+/// `wButtons` bit: D-pad down.
+pub const XINPUT_GAMEPAD_DPAD_DOWN: u16 = 0x0002;
+/// `wButtons` bit: D-pad left.
+pub const XINPUT_GAMEPAD_DPAD_LEFT: u16 = 0x0004;
+/// `wButtons` bit: D-pad right.
+pub const XINPUT_GAMEPAD_DPAD_RIGHT: u16 = 0x0008;
+/// `wButtons` bit: start.
+pub const XINPUT_GAMEPAD_START: u16 = 0x0010;
+/// `wButtons` bit: back.
+pub const XINPUT_GAMEPAD_BACK: u16 = 0x0020;
+/// `wButtons` bit: left thumb stick pressed in.
+pub const XINPUT_GAMEPAD_LEFT_THUMB: u16 = 0x0040;
+/// `wButtons` bit: right thumb stick pressed in.
+pub const XINPUT_GAMEPAD_RIGHT_THUMB: u16 = 0x0080;
+/// `wButtons` bit: left shoulder.
+pub const XINPUT_GAMEPAD_LEFT_SHOULDER: u16 = 0x0100;
+/// `wButtons` bit: right shoulder.
+pub const XINPUT_GAMEPAD_RIGHT_SHOULDER: u16 = 0x0200;
+/// `wButtons` bit: A.
+pub const XINPUT_GAMEPAD_A: u16 = 0x1000;
+/// `wButtons` bit: B.
+pub const XINPUT_GAMEPAD_B: u16 = 0x2000;
+/// `wButtons` bit: X.
+pub const XINPUT_GAMEPAD_X: u16 = 0x4000;
+/// `wButtons` bit: Y.
+pub const XINPUT_GAMEPAD_Y: u16 = 0x8000;
+
+/// Suggested deadzone for the left thumb stick.
+pub const XINPUT_GAMEPAD_LEFT_THUMB_DEADZONE: i16 = 7849;
+/// Suggested deadzone for the right thumb stick.
+pub const XINPUT_GAMEPAD_RIGHT_THUMB_DEADZONE: i16 = 8689;
+/// Suggested threshold for a trigger to count as "pressed".
+pub const XINPUT_GAMEPAD_TRIGGER_THRESHOLD: u8 = 30;
+
+/// Flag for `XInputGetCapabilities` to only report gamepads.
+pub const XINPUT_FLAG_GAMEPAD: u32 = 0x0000_0001;
+
+/// Battery device type: the gamepad itself.
+pub const BATTERY_DEVTYPE_GAMEPAD: u8 = 0x00;
+/// Battery device type: a headset plugged into the gamepad.
+pub const BATTERY_DEVTYPE_HEADSET: u8 = 0x01;
+
+/// Battery type: the device isn't connected.
+pub const BATTERY_TYPE_DISCONNECTED: u8 = 0x00;
+/// Battery type: wired, no battery.
+pub const BATTERY_TYPE_WIRED: u8 = 0x01;
+/// Battery type: alkaline battery.
+pub const BATTERY_TYPE_ALKALINE: u8 = 0x02;
+/// Battery type: nickel metal hydride battery.
+pub const BATTERY_TYPE_NIMH: u8 = 0x03;
+/// Battery type: can't be determined.
+pub const BATTERY_TYPE_UNKNOWN: u8 = 0xFF;
+
+/// Battery level: empty.
+pub const BATTERY_LEVEL_EMPTY: u8 = 0x00;
+/// Battery level: low.
+pub const BATTERY_LEVEL_LOW: u8 = 0x01;
+/// Battery level: medium.
+pub const BATTERY_LEVEL_MEDIUM: u8 = 0x02;
+/// Battery level: full.
+pub const BATTERY_LEVEL_FULL: u8 = 0x03;
+
+/// The number of controller slots that XInput supports.
+pub const XUSER_MAX_COUNT: u32 = 4;
+/// Special `user_index` for `XInputGetKeystroke` meaning "any slot".
+pub const XUSER_INDEX_ANY: u32 = 0x0000_00FF;
+
+/// Keystroke virtual key: A.
+pub const VK_PAD_A: u16 = 0x5800;
+/// Keystroke virtual key: B.
+pub const VK_PAD_B: u16 = 0x5801;
+/// Keystroke virtual key: X.
+pub const VK_PAD_X: u16 = 0x5802;
+/// Keystroke virtual key: Y.
+pub const VK_PAD_Y: u16 = 0x5803;
+/// Keystroke virtual key: right shoulder.
+pub const VK_PAD_RSHOULDER: u16 = 0x5804;
+/// Keystroke virtual key: left shoulder.
+pub const VK_PAD_LSHOULDER: u16 = 0x5805;
+/// Keystroke virtual key: left trigger.
+pub const VK_PAD_LTRIGGER: u16 = 0x5806;
+/// Keystroke virtual key: right trigger.
+pub const VK_PAD_RTRIGGER: u16 = 0x5807;
+/// Keystroke virtual key: D-pad up.
+pub const VK_PAD_DPAD_UP: u16 = 0x5810;
+/// Keystroke virtual key: D-pad down.
+pub const VK_PAD_DPAD_DOWN: u16 = 0x5811;
+/// Keystroke virtual key: D-pad left.
+pub const VK_PAD_DPAD_LEFT: u16 = 0x5812;
+/// Keystroke virtual key: D-pad right.
+pub const VK_PAD_DPAD_RIGHT: u16 = 0x5813;
+/// Keystroke virtual key: start.
+pub const VK_PAD_START: u16 = 0x5814;
+/// Keystroke virtual key: back.
+pub const VK_PAD_BACK: u16 = 0x5815;
+/// Keystroke virtual key: left thumb stick pressed in.
+pub const VK_PAD_LTHUMB_PRESS: u16 = 0x5816;
+/// Keystroke virtual key: right thumb stick pressed in.
+pub const VK_PAD_RTHUMB_PRESS: u16 = 0x5817;
+/// Keystroke virtual key: left thumb stick up.
+pub const VK_PAD_LTHUMB_UP: u16 = 0x5820;
+/// Keystroke virtual key: left thumb stick down.
+pub const VK_PAD_LTHUMB_DOWN: u16 = 0x5821;
+/// Keystroke virtual key: left thumb stick right.
+pub const VK_PAD_LTHUMB_RIGHT: u16 = 0x5822;
+/// Keystroke virtual key: left thumb stick left.
+pub const VK_PAD_LTHUMB_LEFT: u16 = 0x5823;
+/// Keystroke virtual key: left thumb stick up and left.
+pub const VK_PAD_LTHUMB_UPLEFT: u16 = 0x5824;
+/// Keystroke virtual key: left thumb stick up and right.
+pub const VK_PAD_LTHUMB_UPRIGHT: u16 = 0x5825;
+/// Keystroke virtual key: left thumb stick down and right.
+pub const VK_PAD_LTHUMB_DOWNRIGHT: u16 = 0x5826;
+/// Keystroke virtual key: left thumb stick down and left.
+pub const VK_PAD_LTHUMB_DOWNLEFT: u16 = 0x5827;
+/// Keystroke virtual key: right thumb stick up.
+pub const VK_PAD_RTHUMB_UP: u16 = 0x5830;
+/// Keystroke virtual key: right thumb stick down.
+pub const VK_PAD_RTHUMB_DOWN: u16 = 0x5831;
+/// Keystroke virtual key: right thumb stick right.
+pub const VK_PAD_RTHUMB_RIGHT: u16 = 0x5832;
+/// Keystroke virtual key: right thumb stick left.
+pub const VK_PAD_RTHUMB_LEFT: u16 = 0x5833;
+/// Keystroke virtual key: right thumb stick up and left.
+pub const VK_PAD_RTHUMB_UPLEFT: u16 = 0x5834;
+/// Keystroke virtual key: right thumb stick up and right.
+pub const VK_PAD_RTHUMB_UPRIGHT: u16 = 0x5835;
+/// Keystroke virtual key: right thumb stick down and right.
+pub const VK_PAD_RTHUMB_DOWNRIGHT: u16 = 0x5836;
+/// Keystroke virtual key: right thumb stick down and left.
+pub const VK_PAD_RTHUMB_DOWNLEFT: u16 = 0x5837;
+
+/// Keystroke flag: the key was pressed.
+pub const XINPUT_KEYSTROKE_KEYDOWN: u16 = 0x0001;
+/// Keystroke flag: the key was released.
+pub const XINPUT_KEYSTROKE_KEYUP: u16 = 0x0002;
+/// Keystroke flag: the key is being held and this is a repeat.
+pub const XINPUT_KEYSTROKE_REPEAT: u16 = 0x0004;
+
+/// The buttons, triggers, and sticks of a gamepad.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XINPUT_GAMEPAD {
+  /// Bit mask of the `XINPUT_GAMEPAD_*` buttons currently held.
+  pub wButtons: u16,
+  /// Left trigger, 0 to 255.
+  pub bLeftTrigger: u8,
+  /// Right trigger, 0 to 255.
+  pub bRightTrigger: u8,
+  /// Left stick X-axis, negative is left.
+  pub sThumbLX: i16,
+  /// Left stick Y-axis, negative is down.
+  pub sThumbLY: i16,
+  /// Right stick X-axis, negative is left.
+  pub sThumbRX: i16,
+  /// Right stick Y-axis, negative is down.
+  pub sThumbRY: i16,
+}
+
+/// A gamepad state along with its packet number.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XINPUT_STATE {
+  /// Changes whenever the gamepad state changes.
+  pub dwPacketNumber: u32,
+  /// The gamepad state.
+  pub Gamepad: XINPUT_GAMEPAD,
+}
+
+/// Rumble motor speeds.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XINPUT_VIBRATION {
+  /// Low-frequency motor speed, 0 to 65,535.
+  pub wLeftMotorSpeed: u16,
+  /// High-frequency motor speed, 0 to 65,535.
+  pub wRightMotorSpeed: u16,
+}
+
+/// Describes what a controller can do.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XINPUT_CAPABILITIES {
+  /// Always `XINPUT_DEVTYPE_GAMEPAD`.
+  pub Type: u8,
+  /// One of the `XINPUT_DEVSUBTYPE_*` values.
+  pub SubType: u8,
+  /// Bit mask of `XINPUT_CAPS_*` values.
+  pub Flags: u16,
+  /// Each field is non-zero if the device has that input. For the sticks and
+  /// triggers the value is the resolution of the input.
+  pub Gamepad: XINPUT_GAMEPAD,
+  /// Each field is non-zero if the device has that motor, with the value being
+  /// the resolution of the motor.
+  pub Vibration: XINPUT_VIBRATION,
+}
+
+/// Battery info as the DLL reports it.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XINPUT_BATTERY_INFORMATION {
+  /// One of the `BATTERY_TYPE_*` values.
+  pub BatteryType: u8,
+  /// One of the `BATTERY_LEVEL_*` values.
+  pub BatteryLevel: u8,
+}
+
+/// A single gamepad input event.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XINPUT_KEYSTROKE {
+  /// One of the `VK_PAD_*` values.
+  pub VirtualKey: u16,
+  /// Unused, always zero.
+  pub Unicode: u16,
+  /// Bit mask of `XINPUT_KEYSTROKE_*` values.
+  pub Flags: u16,
+  /// The controller slot that generated the event.
+  pub UserIndex: u8,
+  /// HID code of the key, if any.
+  pub HidCode: u8,
+}
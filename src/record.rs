@@ -0,0 +1,342 @@
+//! Recording controller input to a compact binary log.
+//!
+//! ## File Format
+//!
+//! All numbers are little-endian.
+//!
+//! * Header: the magic bytes `RXIR`, then the format version as a `u16`.
+//! * Then any number of frames, each of which is:
+//!   * kind `u8`: 0 for a new state, 1 for a disconnect.
+//!   * slot `u8`
+//!   * timestamp `u64`: microseconds since the recording started.
+//!   * If the kind is 0, the state follows: packet number `u32`, buttons `u16`,
+//!     left trigger `u8`, right trigger `u8`, then `i16` values for left stick
+//!     X and Y and right stick X and Y.
+
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Read, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{
+  ControllerBackend, XInputBatteryInformation, XInputCapabilities, XInputOptionalFnUsageError,
+  XInputState, XInputUsageError, XINPUT_GAMEPAD, XINPUT_KEYSTROKE, XINPUT_STATE,
+};
+
+/// The magic bytes at the start of every recording.
+pub(crate) const RECORDING_MAGIC: [u8; 4] = *b"RXIR";
+
+/// The version of the recording format that this crate writes.
+pub const RECORDING_VERSION: u16 = 1;
+
+pub(crate) const FRAME_KIND_STATE: u8 = 0;
+pub(crate) const FRAME_KIND_DISCONNECTED: u8 = 1;
+
+/// One entry in an input recording.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordedFrame {
+  /// The controller slot this frame is for.
+  pub user_index: u32,
+  /// How long after the start of the recording this frame happened.
+  pub timestamp: Duration,
+  /// The new controller state, or `None` if the controller was disconnected at
+  /// this point in the recording.
+  pub state: Option<XInputState>,
+}
+
+pub(crate) fn write_header<W: Write>(w: &mut W) -> io::Result<()> {
+  w.write_all(&RECORDING_MAGIC)?;
+  w.write_all(&RECORDING_VERSION.to_le_bytes())
+}
+
+pub(crate) fn write_frame<W: Write>(w: &mut W, frame: &RecordedFrame) -> io::Result<()> {
+  let kind = if frame.state.is_some() {
+    FRAME_KIND_STATE
+  } else {
+    FRAME_KIND_DISCONNECTED
+  };
+  let micros = frame.timestamp.as_secs() * 1_000_000 + u64::from(frame.timestamp.subsec_micros());
+  w.write_all(&[kind, frame.user_index as u8])?;
+  w.write_all(&micros.to_le_bytes())?;
+  if let Some(state) = frame.state {
+    let gamepad = &state.raw.Gamepad;
+    w.write_all(&state.raw.dwPacketNumber.to_le_bytes())?;
+    w.write_all(&gamepad.wButtons.to_le_bytes())?;
+    w.write_all(&[gamepad.bLeftTrigger, gamepad.bRightTrigger])?;
+    w.write_all(&gamepad.sThumbLX.to_le_bytes())?;
+    w.write_all(&gamepad.sThumbLY.to_le_bytes())?;
+    w.write_all(&gamepad.sThumbRX.to_le_bytes())?;
+    w.write_all(&gamepad.sThumbRY.to_le_bytes())?;
+  }
+  Ok(())
+}
+
+/// Reads all the frames of a recording made by `InputRecorder`.
+///
+/// ## Failure
+///
+/// Any IO error is passed along. If the data isn't a recording, or if it's a
+/// recording from a newer version of the format than this crate knows about,
+/// you get an `InvalidData` error. A recording that was cut off partway through
+/// a frame (eg: the game crashed) isn't an error, you just get all the frames
+/// that were complete.
+pub fn read_recording<R: Read>(mut r: R) -> io::Result<Vec<RecordedFrame>> {
+  let mut header = [0; 6];
+  r.read_exact(&mut header)?;
+  if header[..4] != RECORDING_MAGIC {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidData,
+      "not an input recording",
+    ));
+  }
+  let version = u16::from_le_bytes([header[4], header[5]]);
+  if version > RECORDING_VERSION {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidData,
+      format!("unsupported input recording version: {}", version),
+    ));
+  }
+  let mut bytes = Vec::new();
+  r.read_to_end(&mut bytes)?;
+
+  let mut frames = Vec::new();
+  let mut rest = &bytes[..];
+  while rest.len() >= 10 {
+    let kind = rest[0];
+    let user_index = u32::from(rest[1]);
+    let mut micros = [0; 8];
+    micros.copy_from_slice(&rest[2..10]);
+    let timestamp = Duration::from_micros(u64::from_le_bytes(micros));
+    let state = match kind {
+      FRAME_KIND_STATE if rest.len() >= 26 => {
+        let b = &rest[10..26];
+        let u16_at = |i: usize| u16::from_le_bytes([b[i], b[i + 1]]);
+        Some(XInputState {
+          raw: XINPUT_STATE {
+            dwPacketNumber: u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            Gamepad: XINPUT_GAMEPAD {
+              wButtons: u16_at(4),
+              bLeftTrigger: b[6],
+              bRightTrigger: b[7],
+              sThumbLX: u16_at(8) as i16,
+              sThumbLY: u16_at(10) as i16,
+              sThumbRX: u16_at(12) as i16,
+              sThumbRY: u16_at(14) as i16,
+            },
+          },
+        })
+      }
+      FRAME_KIND_STATE => break,
+      FRAME_KIND_DISCONNECTED => None,
+      k => {
+        return Err(io::Error::new(
+          io::ErrorKind::InvalidData,
+          format!("unknown input recording frame kind: {}", k),
+        ))
+      }
+    };
+    rest = &rest[if state.is_some() { 26 } else { 10 }..];
+    if user_index >= 4 {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid slot in input recording: {}", user_index),
+      ));
+    }
+    frames.push(RecordedFrame {
+      user_index,
+      timestamp,
+      state,
+    });
+  }
+  Ok(frames)
+}
+
+/// What we last wrote for a slot.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SlotRecord {
+  Nothing,
+  Disconnected,
+  Packet(u32),
+}
+
+struct RecorderInner<W> {
+  writer: W,
+  slots: [SlotRecord; 4],
+  error: Option<io::Error>,
+}
+
+/// Wraps a `ControllerBackend` and writes every changed controller state to a
+/// binary log.
+///
+/// You use the recorder in place of the backend it wraps. Each `get_state` or
+/// `get_state_ex` call is passed along, and if the packet number for that slot
+/// changed since what was last written (or the controller was disconnected) a
+/// new frame is written out. All other calls are passed along without being
+/// recorded, and so are states for a `user_index` of 4 or more.
+///
+/// Timestamps are measured with `Instant`, starting from when the recorder was
+/// created.
+///
+/// ## Failure
+///
+/// An IO error doesn't stop the polling itself, since you don't want the game
+/// to lose input just because the disk is full. Instead, the first error is
+/// kept, nothing more is written, and `finish` will report the error.
+pub struct InputRecorder<B, W: Write> {
+  backend: B,
+  start: Instant,
+  inner: Mutex<RecorderInner<W>>,
+}
+
+impl<B, W: Write> Debug for InputRecorder<B, W> {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    write!(f, "InputRecorder(elapsed = {:?})", self.start.elapsed())
+  }
+}
+
+impl<B: ControllerBackend, W: Write> InputRecorder<B, W> {
+  /// Starts a new recording, writing the file header right away.
+  pub fn new(backend: B, mut writer: W) -> io::Result<Self> {
+    write_header(&mut writer)?;
+    Ok(InputRecorder {
+      backend,
+      start: Instant::now(),
+      inner: Mutex::new(RecorderInner {
+        writer,
+        slots: [SlotRecord::Nothing; 4],
+        error: None,
+      }),
+    })
+  }
+
+  /// The backend being recorded.
+  pub fn backend(&self) -> &B {
+    &self.backend
+  }
+
+  /// Ends the recording, giving back the backend and the writer.
+  ///
+  /// ## Failure
+  ///
+  /// If any write failed during the recording, that first error is returned
+  /// here. Otherwise, an error flushing the writer is returned.
+  pub fn finish(self) -> io::Result<(B, W)> {
+    let mut inner = self
+      .inner
+      .into_inner()
+      .unwrap_or_else(|poison| poison.into_inner());
+    if let Some(e) = inner.error.take() {
+      return Err(e);
+    }
+    inner.writer.flush()?;
+    Ok((self.backend, inner.writer))
+  }
+
+  fn record(&self, user_index: u32, result: &Result<XInputState, XInputUsageError>) {
+    // only a real slot could ever be replayed, whatever the backend says.
+    if user_index >= 4 {
+      return;
+    }
+    let now = match *result {
+      Ok(state) => SlotRecord::Packet(state.raw.dwPacketNumber),
+      Err(XInputUsageError::DeviceNotConnected) => SlotRecord::Disconnected,
+      // other errors don't tell us anything about the controller.
+      Err(_) => return,
+    };
+    let timestamp = self.start.elapsed();
+    let mut inner = self
+      .inner
+      .lock()
+      .unwrap_or_else(|poison| poison.into_inner());
+    let inner = &mut *inner;
+    let slot = &mut inner.slots[user_index as usize];
+    if inner.error.is_none() && *slot != now {
+      let frame = RecordedFrame {
+        user_index,
+        timestamp,
+        state: result.ok(),
+      };
+      match write_frame(&mut inner.writer, &frame) {
+        Ok(()) => *slot = now,
+        Err(e) => {
+          debug!("Input recording failed: {}", e);
+          inner.error = Some(e);
+        }
+      }
+    }
+  }
+}
+
+impl<B: ControllerBackend, W: Write> ControllerBackend for InputRecorder<B, W> {
+  fn get_state(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    let result = self.backend.get_state(user_index);
+    self.record(user_index, &result);
+    result
+  }
+
+  fn get_state_ex(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    let result = self.backend.get_state_ex(user_index);
+    self.record(user_index, &result);
+    result
+  }
+
+  fn set_state(
+    &self,
+    user_index: u32,
+    left_motor_speed: u16,
+    right_motor_speed: u16,
+  ) -> Result<(), XInputUsageError> {
+    self
+      .backend
+      .set_state(user_index, left_motor_speed, right_motor_speed)
+  }
+
+  fn get_capabilities(&self, user_index: u32) -> Result<XInputCapabilities, XInputUsageError> {
+    self.backend.get_capabilities(user_index)
+  }
+
+  fn get_keystroke(
+    &self,
+    user_index: u32,
+  ) -> Result<Option<XINPUT_KEYSTROKE>, XInputOptionalFnUsageError> {
+    self.backend.get_keystroke(user_index)
+  }
+
+  fn get_gamepad_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    self.backend.get_gamepad_battery_information(user_index)
+  }
+
+  fn get_headset_battery_information(
+    &self,
+    user_index: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    self.backend.get_headset_battery_information(user_index)
+  }
+}
+
+#[test]
+fn input_recorder_test() {
+  use super::{MockBackend, XINPUT_GAMEPAD_A};
+
+  let mock = MockBackend::new();
+  mock.connect(1);
+  let recorder = InputRecorder::new(&mock, Vec::new()).unwrap();
+  // connected, unchanged, changed, disconnected, still disconnected
+  recorder.get_state(1).unwrap();
+  recorder.get_state(1).unwrap();
+  mock.press_buttons(1, XINPUT_GAMEPAD_A);
+  recorder.get_state_ex(1).unwrap();
+  mock.disconnect(1);
+  recorder.get_state(1).unwrap_err();
+  recorder.get_state(1).unwrap_err();
+  // out of range slots are never recorded, even if a backend has them
+  recorder.get_state(7).unwrap_err();
+  recorder.record(7, &Ok(XInputState::default()));
+
+  let (_, bytes) = recorder.finish().unwrap();
+  assert_eq!(&bytes[..4], b"RXIR");
+  assert_eq!(bytes.len(), 6 + 2 * (10 + 16) + 10);
+}
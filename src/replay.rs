@@ -0,0 +1,294 @@
+//! Playing an input recording back as if it were live controllers.
+
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Read};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::backend::wired_gamepad_capabilities;
+use super::{
+  read_recording, ControllerBackend, RecordedFrame, XInputBatteryInformation, XInputCapabilities,
+  XInputOptionalFnUsageError, XInputState, XInputUsageError, XINPUT_GAMEPAD_GUIDE,
+  XINPUT_KEYSTROKE,
+};
+
+/// How a `ReplayBackend` moves through its recording.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum ReplayMode {
+  /// The recording plays at the same speed it was recorded, starting when the
+  /// replay is created. You can still skip ahead with `step` and `advance`.
+  RealTime,
+  /// Time only passes when you call `step` or `advance`. This is what you want
+  /// for deterministic tests.
+  Stepped,
+}
+
+struct ReplayCursor {
+  start: Instant,
+  offset: Duration,
+  next_frame: usize,
+  slots: [Option<XInputState>; 4],
+}
+
+/// A `ControllerBackend` that plays back frames from an `InputRecorder`.
+///
+/// Each slot reports the most recent recorded state at or before the current
+/// replay position. Before a slot's first frame, and after any disconnect
+/// frame, the slot reports `DeviceNotConnected`.
+///
+/// Recordings only hold gamepad states, so the other calls do the best they
+/// can: `set_state` is accepted and ignored, `get_capabilities` reports a plain
+/// wired gamepad, `get_keystroke` never has any keystrokes, and the battery
+/// calls give `FunctionNotLoaded`.
+pub struct ReplayBackend {
+  frames: Vec<RecordedFrame>,
+  mode: ReplayMode,
+  cursor: Mutex<ReplayCursor>,
+}
+
+impl Debug for ReplayBackend {
+  fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+    write!(
+      f,
+      "ReplayBackend(mode = {:?}, frames = {}, position = {:?})",
+      self.mode,
+      self.frames.len(),
+      self.position()
+    )
+  }
+}
+
+impl ReplayBackend {
+  /// Makes a replay of the frames given.
+  ///
+  /// The frames are sorted by timestamp (frames with the same timestamp keep
+  /// their order), and any frame with a `user_index` of 4 or more is dropped,
+  /// since it isn't a slot that could ever be polled.
+  pub fn new(mut frames: Vec<RecordedFrame>, mode: ReplayMode) -> Self {
+    frames.retain(|frame| frame.user_index < 4);
+    frames.sort_by_key(|frame| frame.timestamp);
+    ReplayBackend {
+      frames,
+      mode,
+      cursor: Mutex::new(ReplayCursor {
+        start: Instant::now(),
+        offset: Duration::from_secs(0),
+        next_frame: 0,
+        slots: [None; 4],
+      }),
+    }
+  }
+
+  /// Reads a recording and makes a replay of it.
+  ///
+  /// See `read_recording` for the possible errors.
+  pub fn from_reader<R: Read>(r: R, mode: ReplayMode) -> io::Result<Self> {
+    Ok(ReplayBackend::new(read_recording(r)?, mode))
+  }
+
+  /// The mode this replay is running in.
+  pub fn mode(&self) -> ReplayMode {
+    self.mode
+  }
+
+  /// All the frames of the replay.
+  pub fn frames(&self) -> &[RecordedFrame] {
+    &self.frames
+  }
+
+  fn lock(&self) -> MutexGuard<'_, ReplayCursor> {
+    self
+      .cursor
+      .lock()
+      .unwrap_or_else(|poison| poison.into_inner())
+  }
+
+  fn position_of(&self, cursor: &ReplayCursor) -> Duration {
+    match self.mode {
+      ReplayMode::RealTime => cursor.offset + cursor.start.elapsed(),
+      ReplayMode::Stepped => cursor.offset,
+    }
+  }
+
+  /// Applies every frame up to the current position.
+  fn catch_up(&self, cursor: &mut ReplayCursor) {
+    let position = self.position_of(cursor);
+    while let Some(frame) = self.frames.get(cursor.next_frame) {
+      if frame.timestamp > position {
+        break;
+      }
+      cursor.slots[frame.user_index as usize] = frame.state;
+      cursor.next_frame += 1;
+    }
+  }
+
+  /// How far into the recording the replay currently is.
+  pub fn position(&self) -> Duration {
+    self.position_of(&self.lock())
+  }
+
+  /// If every frame of the recording has been played.
+  pub fn is_finished(&self) -> bool {
+    let mut cursor = self.lock();
+    self.catch_up(&mut cursor);
+    cursor.next_frame >= self.frames.len()
+  }
+
+  /// Moves the replay ahead by the amount of time given.
+  ///
+  /// With `Stepped` mode you'd usually call this once per game frame with your
+  /// frame time.
+  pub fn advance(&self, by: Duration) {
+    let mut cursor = self.lock();
+    cursor.offset += by;
+    self.catch_up(&mut cursor);
+  }
+
+  /// Jumps ahead to the next recorded frame, applying it (along with any other
+  /// frames that have the same timestamp).
+  ///
+  /// Returns `false` if the replay was already finished.
+  pub fn step(&self) -> bool {
+    let mut cursor = self.lock();
+    self.catch_up(&mut cursor);
+    match self.frames.get(cursor.next_frame) {
+      Some(frame) => {
+        let position = self.position_of(&cursor);
+        if frame.timestamp > position {
+          cursor.offset += frame.timestamp - position;
+        }
+        self.catch_up(&mut cursor);
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Goes back to the start of the recording, with all slots disconnected.
+  pub fn restart(&self) {
+    let mut cursor = self.lock();
+    cursor.start = Instant::now();
+    cursor.offset = Duration::from_secs(0);
+    cursor.next_frame = 0;
+    cursor.slots = [None; 4];
+  }
+
+  fn current_state(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    if user_index >= 4 {
+      Err(XInputUsageError::InvalidControllerID)
+    } else {
+      let mut cursor = self.lock();
+      self.catch_up(&mut cursor);
+      cursor.slots[user_index as usize].ok_or(XInputUsageError::DeviceNotConnected)
+    }
+  }
+}
+
+impl ControllerBackend for ReplayBackend {
+  fn get_state(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    // The recording might have come from `get_state_ex`.
+    self.current_state(user_index).map(|mut state| {
+      state.raw.Gamepad.wButtons &= !XINPUT_GAMEPAD_GUIDE;
+      state
+    })
+  }
+
+  fn get_state_ex(&self, user_index: u32) -> Result<XInputState, XInputUsageError> {
+    self.current_state(user_index)
+  }
+
+  fn set_state(&self, user_index: u32, _: u16, _: u16) -> Result<(), XInputUsageError> {
+    self.current_state(user_index).map(|_| ())
+  }
+
+  fn get_capabilities(&self, user_index: u32) -> Result<XInputCapabilities, XInputUsageError> {
+    self
+      .current_state(user_index)
+      .map(|_| XInputCapabilities::from(wired_gamepad_capabilities()))
+  }
+
+  fn get_keystroke(
+    &self,
+    user_index: u32,
+  ) -> Result<Option<XINPUT_KEYSTROKE>, XInputOptionalFnUsageError> {
+    match self.current_state(user_index) {
+      Ok(_) => Ok(None),
+      Err(XInputUsageError::InvalidControllerID) => {
+        Err(XInputOptionalFnUsageError::InvalidControllerID)
+      }
+      Err(_) => Err(XInputOptionalFnUsageError::DeviceNotConnected),
+    }
+  }
+
+  fn get_gamepad_battery_information(
+    &self,
+    _: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    Err(XInputOptionalFnUsageError::FunctionNotLoaded)
+  }
+
+  fn get_headset_battery_information(
+    &self,
+    _: u32,
+  ) -> Result<XInputBatteryInformation, XInputOptionalFnUsageError> {
+    Err(XInputOptionalFnUsageError::FunctionNotLoaded)
+  }
+}
+
+#[test]
+fn replay_backend_test() {
+  use super::record::{write_frame, write_header};
+  use super::XINPUT_GAMEPAD_B;
+
+  let mut pressed = XInputState::default();
+  pressed.raw.dwPacketNumber = 5;
+  pressed.raw.Gamepad.wButtons = XINPUT_GAMEPAD_B | XINPUT_GAMEPAD_GUIDE;
+  let mut bytes = Vec::new();
+  write_header(&mut bytes).unwrap();
+  for &(millis, state) in &[(10, Some(XInputState::default())), (20, Some(pressed)), (30, None)] {
+    let frame = RecordedFrame {
+      user_index: 2,
+      timestamp: Duration::from_millis(millis),
+      state,
+    };
+    write_frame(&mut bytes, &frame).unwrap();
+  }
+
+  let replay = ReplayBackend::from_reader(&bytes[..], ReplayMode::Stepped).unwrap();
+  assert_eq!(replay.frames().len(), 3);
+  assert_eq!(
+    replay.get_state(2),
+    Err(XInputUsageError::DeviceNotConnected)
+  );
+  replay.advance(Duration::from_millis(15));
+  assert!(!replay.get_state(2).unwrap().east_button());
+  assert!(replay.step());
+  assert_eq!(replay.position(), Duration::from_millis(20));
+  let state = replay.get_state(2).unwrap();
+  assert!(state.east_button() && !state.guide_button());
+  assert!(replay.get_state_ex(2).unwrap().guide_button());
+  assert!(replay.step());
+  assert_eq!(
+    replay.get_state(2),
+    Err(XInputUsageError::DeviceNotConnected)
+  );
+  assert!(!replay.step());
+  assert!(replay.is_finished());
+
+  // frames out of order are sorted, and frames for bad slots are dropped
+  let frame = |user_index: u32, millis: u64| RecordedFrame {
+    user_index,
+    timestamp: Duration::from_millis(millis),
+    state: Some(pressed),
+  };
+  let replay = ReplayBackend::new(
+    vec![frame(1, 20), frame(9, 5), frame(0, 10)],
+    ReplayMode::Stepped,
+  );
+  assert_eq!(replay.frames().len(), 2);
+  replay.advance(Duration::from_millis(10));
+  assert!(replay.get_state(0).is_ok());
+  assert!(replay.get_state(1).is_err());
+  replay.advance(Duration::from_millis(10));
+  assert!(replay.get_state(1).is_ok());
+}
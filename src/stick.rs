@@ -0,0 +1,469 @@
+//! Turning raw stick values into something you can use.
+
+use super::XInputState;
+
+/// The shape of the deadzone used by a `StickDeadzone`.
+///
+/// Each mode is a trade off. The radial modes treat all directions the same,
+/// which is what you want for camera control and character movement. The
+/// axial modes snap to the axes when the stick is close to them, which is
+/// what you want for menus and for games where you mostly move in straight
+/// lines.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum DeadzoneMode {
+  /// Each axis is zeroed on its own while it's inside that axis's threshold.
+  /// Outside the threshold the value is passed along as is, so there's a jump
+  /// from 0 to the threshold value.
+  Axial,
+  /// Like `Axial`, but each axis is rescaled so that it goes smoothly from 0
+  /// at the threshold up to 1 at full tilt.
+  ScaledAxial,
+  /// The stick is zeroed while it's inside the deadzone, and passed along as
+  /// is otherwise. There's a jump at the edge of the deadzone.
+  Radial,
+  /// Like `Radial`, but the length is rescaled so that it goes smoothly from 0
+  /// at the edge of the deadzone up to 1 at full tilt. This is what
+  /// `normalize_raw_stick_value` does.
+  ScaledRadial,
+  /// Each axis has a deadzone that grows as the other axis is pushed further.
+  /// This snaps to the axes when the stick is pushed almost straight along
+  /// one, but still lets you make small movements near the center.
+  SlopedAxial,
+  /// Like `SlopedAxial`, but each axis is rescaled like `ScaledAxial`. The
+  /// deadzone has a "bowtie" shape.
+  SlopedScaledAxial,
+  /// A `ScaledRadial` deadzone followed by a `SlopedScaledAxial` one. You get
+  /// no drift near the center and also snapping near the axes.
+  Hybrid,
+}
+
+/// A deadzone setting for one stick.
+///
+/// The thresholds use the same units as the raw stick values, and they're
+/// each clamped to the range 0 to 32,766 (inclusive) before use. With the
+/// radial modes, different `x` and `y` thresholds make an elliptical
+/// deadzone.
+///
+/// The output of every mode is in the range -1.0 to 1.0 on each axis. The
+/// radial modes also keep the total length at no more than 1.0, but the axial
+/// modes can reach 1.0 on both axes at once when the stick is in a corner.
+///
+/// There's two more settings that apply no matter the mode:
+///
+/// * `outer` is how far the stick has to be pushed to count as full tilt. By
+///   default this is the largest raw value, but worn out sticks and some third
+///   party pads never get that far.
+/// * `anti_deadzone` is the smallest non-zero output, from 0.0 to 1.0. As soon
+///   as the stick leaves the deadzone the output jumps to this value, and the
+///   rest of the range is squished to fit above it. Use this when you're
+///   feeding the value to a game that has its own deadzone that you want to
+///   get past. The radial modes apply it to the length of the stick, the
+///   axial modes (and `Hybrid`) apply it to each axis. The unscaled modes
+///   don't start from 0 at the edge of the deadzone, so with an anti-deadzone
+///   they're rescaled first, which makes them act the same as their scaled
+///   versions.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct StickDeadzone {
+  /// The shape of the deadzone.
+  pub mode: DeadzoneMode,
+  /// The threshold along the X-axis.
+  pub x: i16,
+  /// The threshold along the Y-axis.
+  pub y: i16,
+  /// The raw distance that counts as full tilt.
+  ///
+  /// This is raised to one more than the largest threshold if it's smaller
+  /// than that.
+  pub outer: i16,
+  /// The smallest non-zero output, clamped to the range 0.0 to 1.0 before use.
+  /// A value that isn't finite counts as 0.0.
+  pub anti_deadzone: f32,
+}
+
+impl StickDeadzone {
+  /// The default deadzone for the left stick, the same as
+  /// `left_stick_normalized` uses.
+  pub const LEFT_DEFAULT: Self = StickDeadzone {
+    mode: DeadzoneMode::ScaledRadial,
+    x: XInputState::LEFT_STICK_DEADZONE,
+    y: XInputState::LEFT_STICK_DEADZONE,
+    outer: i16::MAX,
+    anti_deadzone: 0.0,
+  };
+
+  /// The default deadzone for the right stick, the same as
+  /// `right_stick_normalized` uses.
+  pub const RIGHT_DEFAULT: Self = StickDeadzone {
+    mode: DeadzoneMode::ScaledRadial,
+    x: XInputState::RIGHT_STICK_DEADZONE,
+    y: XInputState::RIGHT_STICK_DEADZONE,
+    outer: i16::MAX,
+    anti_deadzone: 0.0,
+  };
+
+  /// A deadzone with the same threshold on both axes.
+  #[inline]
+  pub fn new(mode: DeadzoneMode, threshold: i16) -> Self {
+    StickDeadzone::per_axis(mode, threshold, threshold)
+  }
+
+  /// A deadzone with a different threshold for each axis.
+  #[inline]
+  pub fn per_axis(mode: DeadzoneMode, x: i16, y: i16) -> Self {
+    StickDeadzone {
+      mode,
+      x,
+      y,
+      outer: i16::MAX,
+      anti_deadzone: 0.0,
+    }
+  }
+
+  /// Sets the raw distance that counts as full tilt.
+  #[inline]
+  pub fn with_outer(mut self, outer: i16) -> Self {
+    self.outer = outer;
+    self
+  }
+
+  /// Sets the smallest non-zero output.
+  #[inline]
+  pub fn with_anti_deadzone(mut self, anti_deadzone: f32) -> Self {
+    self.anti_deadzone = anti_deadzone;
+    self
+  }
+
+  /// Applies this deadzone to a raw stick value.
+  ///
+  /// Positive values are to the right (X-axis) or up (Y-axis).
+  pub fn apply(&self, raw_stick: (i16, i16)) -> (f32, f32) {
+    // Everything is measured in units of the outer distance.
+    let x = self.x.clamp(0, i16::MAX - 1);
+    let y = self.y.clamp(0, i16::MAX - 1);
+    let outer = f32::from(self.outer.max(x.max(y) + 1));
+    let stick = (
+      (f32::from(raw_stick.0) / outer).clamp(-1.0, 1.0),
+      (f32::from(raw_stick.1) / outer).clamp(-1.0, 1.0),
+    );
+    let dz = (f32::from(x) / outer, f32::from(y) / outer);
+    let anti = if self.anti_deadzone.is_finite() {
+      self.anti_deadzone.clamp(0.0, 1.0)
+    } else {
+      0.0
+    };
+    // The anti-deadzone has to start from 0 at the edge of the deadzone.
+    let mode = if anti == 0.0 {
+      self.mode
+    } else {
+      self.mode.scaled()
+    };
+    let output = match mode {
+      DeadzoneMode::Axial => (axial(stick.0, dz.0), axial(stick.1, dz.1)),
+      DeadzoneMode::ScaledAxial => (scaled_axial(stick.0, dz.0), scaled_axial(stick.1, dz.1)),
+      DeadzoneMode::Radial => radial(stick, dz, false),
+      DeadzoneMode::ScaledRadial => radial(stick, dz, true),
+      DeadzoneMode::SlopedAxial => (
+        axial(stick.0, dz.0 * stick.1.abs()),
+        axial(stick.1, dz.1 * stick.0.abs()),
+      ),
+      DeadzoneMode::SlopedScaledAxial => sloped_scaled_axial(stick, dz),
+      DeadzoneMode::Hybrid => sloped_scaled_axial(radial(stick, dz, true), dz),
+    };
+    if anti == 0.0 {
+      return output;
+    }
+    match mode {
+      DeadzoneMode::ScaledRadial => {
+        let length = (output.0 * output.0 + output.1 * output.1).sqrt();
+        if length == 0.0 {
+          output
+        } else {
+          let scale = lift(length, anti) / length;
+          (output.0 * scale, output.1 * scale)
+        }
+      }
+      _ => (
+        lift(output.0.abs(), anti).copysign(output.0),
+        lift(output.1.abs(), anti).copysign(output.1),
+      ),
+    }
+  }
+}
+
+impl DeadzoneMode {
+  /// The version of this mode that rescales from the edge of the deadzone.
+  fn scaled(self) -> Self {
+    match self {
+      DeadzoneMode::Axial => DeadzoneMode::ScaledAxial,
+      DeadzoneMode::Radial => DeadzoneMode::ScaledRadial,
+      DeadzoneMode::SlopedAxial => DeadzoneMode::SlopedScaledAxial,
+      other => other,
+    }
+  }
+}
+
+impl Default for StickDeadzone {
+  /// `LEFT_DEFAULT`
+  fn default() -> Self {
+    StickDeadzone::LEFT_DEFAULT
+  }
+}
+
+/// Snaps a normalized stick value onto the nearest axis (or diagonal) when
+/// it's close to one, keeping the length the same.
+///
+/// Use this on the output of a deadzone, eg:
+/// `snap.apply(state.left_stick_normalized())`.
+///
+/// An angle within `cardinal_window` degrees of up, down, left, or right is
+/// snapped exactly onto that axis, and an angle within `diagonal_window`
+/// degrees of a diagonal is snapped exactly onto that diagonal. The angles in
+/// between are spread out to fill the gap, so there's no sudden jump when the
+/// stick leaves a window.
+///
+/// With the `serde` feature enabled this can be serialized.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct AngularSnap {
+  /// Degrees on each side of a cardinal direction that snap onto it, clamped
+  /// to the range 0 to 45. A value that isn't finite counts as 0.
+  pub cardinal_window: f32,
+  /// Degrees on each side of a diagonal that snap onto it. This is clamped so
+  /// that it doesn't overlap the cardinal window. Use 0 for no diagonal
+  /// snapping. A value that isn't finite counts as 0.
+  pub diagonal_window: f32,
+}
+
+impl AngularSnap {
+  /// Snapping to the cardinal directions only.
+  #[inline]
+  pub fn new(cardinal_window: f32) -> Self {
+    AngularSnap {
+      cardinal_window,
+      diagonal_window: 0.0,
+    }
+  }
+
+  /// Sets the window for the diagonals.
+  #[inline]
+  pub fn with_diagonals(mut self, diagonal_window: f32) -> Self {
+    self.diagonal_window = diagonal_window;
+    self
+  }
+
+  /// Applies the snapping to a normalized stick value.
+  pub fn apply(&self, stick: (f32, f32)) -> (f32, f32) {
+    let length = (stick.0 * stick.0 + stick.1 * stick.1).sqrt();
+    if length.is_nan() || length == 0.0 {
+      return (0.0, 0.0);
+    }
+    let finite = |window: f32| if window.is_finite() { window } else { 0.0 };
+    let cardinal = finite(self.cardinal_window).clamp(0.0, 45.0);
+    let diagonal = finite(self.diagonal_window).clamp(0.0, 45.0 - cardinal);
+    let angle = stick.1.atan2(stick.0).to_degrees().rem_euclid(360.0);
+    // Which 45 degree slice we're in, and how far into it. Even slices start
+    // at a cardinal direction, odd ones at a diagonal.
+    let slice = ((angle / 45.0) as u32).min(7);
+    let offset = angle - slice as f32 * 45.0;
+    let (start_window, end_window) = if slice & 1 == 0 {
+      (cardinal, diagonal)
+    } else {
+      (diagonal, cardinal)
+    };
+    let unit = if offset <= start_window {
+      eighth_turn(slice)
+    } else if offset >= 45.0 - end_window {
+      eighth_turn(slice + 1)
+    } else {
+      let spread = (offset - start_window) / (45.0 - start_window - end_window) * 45.0;
+      let radians = (slice as f32 * 45.0 + spread).to_radians();
+      (radians.cos(), radians.sin())
+    };
+    (unit.0 * length, unit.1 * length)
+  }
+}
+
+/// The unit vector `n` eighths of a turn counter-clockwise from the right,
+/// with exact zeroes.
+fn eighth_turn(n: u32) -> (f32, f32) {
+  use std::f32::consts::FRAC_1_SQRT_2 as D;
+  match n % 8 {
+    0 => (1.0, 0.0),
+    1 => (D, D),
+    2 => (0.0, 1.0),
+    3 => (-D, D),
+    4 => (-1.0, 0.0),
+    5 => (-D, -D),
+    6 => (0.0, -1.0),
+    _ => (D, -D),
+  }
+}
+
+/// Moves a non-zero magnitude from `0.0..=1.0` up into `anti..=1.0`.
+fn lift(magnitude: f32, anti: f32) -> f32 {
+  if magnitude == 0.0 {
+    0.0
+  } else {
+    anti + (1.0 - anti) * magnitude
+  }
+}
+
+fn axial(value: f32, deadzone: f32) -> f32 {
+  if value.abs() > deadzone {
+    value
+  } else {
+    0.0
+  }
+}
+
+/// Rescales from the range `deadzone..=1.0` to `0.0..=1.0`, keeping the sign.
+fn scaled_axial(value: f32, deadzone: f32) -> f32 {
+  if value.abs() > deadzone {
+    let scaled = (value.abs() - deadzone) / (1.0 - deadzone);
+    scaled.min(1.0).copysign(value)
+  } else {
+    0.0
+  }
+}
+
+fn sloped_scaled_axial(stick: (f32, f32), dz: (f32, f32)) -> (f32, f32) {
+  (
+    scaled_axial(stick.0, dz.0 * stick.1.abs()),
+    scaled_axial(stick.1, dz.1 * stick.0.abs()),
+  )
+}
+
+fn radial(stick: (f32, f32), dz: (f32, f32), scaled: bool) -> (f32, f32) {
+  let length = (stick.0 * stick.0 + stick.1 * stick.1).sqrt();
+  if length == 0.0 {
+    return (0.0, 0.0);
+  }
+  let direction = (stick.0 / length, stick.1 / length);
+  // The distance from the center to the edge of the deadzone ellipse, going
+  // in the stick's direction. A zero threshold on an axis makes the ellipse
+  // flat, so any movement off of that axis is outside of it.
+  let term = |n: f32, d: f32| if n == 0.0 { 0.0 } else { (n / d).powi(2) };
+  let edge = 1.0 / (term(direction.0, dz.0) + term(direction.1, dz.1)).sqrt();
+  let length = length.min(1.0);
+  if length > edge {
+    let out_length = if scaled {
+      (length - edge) / (1.0 - edge)
+    } else {
+      length
+    };
+    (direction.0 * out_length, direction.1 * out_length)
+  } else {
+    (0.0, 0.0)
+  }
+}
+
+#[test]
+fn stick_deadzone_test() {
+  let close = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4;
+  let max = i16::MAX;
+
+  // the default is the same as the old fixed algorithm
+  for &raw in &[(0, 0), (5000, 5000), (max, 0), (-20_000, 12_000), (i16::MIN, i16::MIN)] {
+    assert!(close(
+      StickDeadzone::LEFT_DEFAULT.apply(raw),
+      XInputState::normalize_raw_stick_value(raw, XInputState::LEFT_STICK_DEADZONE)
+    ));
+  }
+
+  let axial = StickDeadzone::new(DeadzoneMode::Axial, 8000);
+  assert!(close(axial.apply((max, 7000)), (1.0, 0.0)));
+  assert!(close(axial.apply((9000, 0)), (9000.0 / 32_767.0, 0.0)));
+  let scaled_axial = StickDeadzone::new(DeadzoneMode::ScaledAxial, 8000);
+  assert!(close(scaled_axial.apply((max, -max)), (1.0, -1.0)));
+  assert!(close(scaled_axial.apply((8001, 0)), (0.0, 0.0)));
+
+  let radial = StickDeadzone::new(DeadzoneMode::Radial, 8000);
+  assert!(close(
+    radial.apply((6000, 6000)),
+    (6000.0 / 32_767.0, 6000.0 / 32_767.0)
+  ));
+  assert!(close(radial.apply((5000, 5000)), (0.0, 0.0)));
+
+  // an elliptical deadzone that's wide on X and narrow on Y
+  let ellipse = StickDeadzone::per_axis(DeadzoneMode::ScaledRadial, 16_000, 4000);
+  assert!(close(ellipse.apply((15_000, 0)), (0.0, 0.0)));
+  assert!(ellipse.apply((0, 5000)).1 > 0.0);
+  assert!(close(ellipse.apply((0, max)), (0.0, 1.0)));
+
+  // sloped modes snap when close to an axis, but not near the center
+  let sloped = StickDeadzone::new(DeadzoneMode::SlopedAxial, 8000);
+  assert!(close(sloped.apply((1500, max)), (0.0, 1.0)));
+  assert!(sloped.apply((1500, 1500)).0 > 0.0);
+  let bowtie = StickDeadzone::new(DeadzoneMode::SlopedScaledAxial, 8000);
+  assert!(close(bowtie.apply((1500, max)), (0.0, 1.0)));
+
+  let hybrid = StickDeadzone::new(DeadzoneMode::Hybrid, 8000);
+  assert!(close(hybrid.apply((1500, 1500)), (0.0, 0.0)));
+  let snapped = hybrid.apply((3000, max));
+  assert!(snapped.0 == 0.0 && snapped.1 > 0.99);
+
+  // a worn stick that only gets to 25,000 can still reach full tilt
+  let worn = StickDeadzone::LEFT_DEFAULT.with_outer(25_000);
+  assert!(close(worn.apply((25_000, 0)), (1.0, 0.0)));
+  assert!(close(worn.apply((0, -30_000)), (0.0, -1.0)));
+  assert!(worn.apply((20_000, 0)).0 < 1.0);
+  // an outer value inside the deadzone is raised to just past it
+  let tiny = StickDeadzone::new(DeadzoneMode::ScaledAxial, 8000).with_outer(10);
+  assert!(close(tiny.apply((8000, 8001)), (0.0, 1.0)));
+
+  let anti = StickDeadzone::new(DeadzoneMode::ScaledRadial, 8000).with_anti_deadzone(0.2);
+  assert!(close(anti.apply((5000, 0)), (0.0, 0.0)));
+  let barely = anti.apply((0, 8100));
+  assert!(barely.0 == 0.0 && barely.1 > 0.2 && barely.1 < 0.21);
+  assert!(close(anti.apply((max, 0)), (1.0, 0.0)));
+  let anti_axial = StickDeadzone::new(DeadzoneMode::ScaledAxial, 8000).with_anti_deadzone(0.5);
+  let nudged = anti_axial.apply((-8100, 0));
+  assert!(nudged.0 < -0.5 && nudged.0 > -0.51 && nudged.1 == 0.0);
+  // unscaled modes also start right at the anti-deadzone
+  let anti_radial = StickDeadzone::new(DeadzoneMode::Radial, 8000).with_anti_deadzone(0.3);
+  let barely = anti_radial.apply((8100, 0));
+  assert!(barely.0 > 0.3 && barely.0 < 0.31 && barely.1 == 0.0);
+  assert!(close(anti_radial.apply((max, 0)), (1.0, 0.0)));
+  let anti_sloped = StickDeadzone::new(DeadzoneMode::SlopedAxial, 8000).with_anti_deadzone(0.3);
+  let barely = anti_sloped.apply((max, -8100));
+  assert!(barely.0 == 1.0 && barely.1 < -0.3 && barely.1 > -0.31);
+  // a NaN anti-deadzone is ignored
+  let nan = StickDeadzone::new(DeadzoneMode::Axial, 8000).with_anti_deadzone(f32::NAN);
+  assert!(close(nan.apply((9000, 0)), axial.apply((9000, 0))));
+}
+
+#[test]
+fn angular_snap_test() {
+  let close = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4;
+  let polar = |degrees: f32, length: f32| {
+    let r = degrees.to_radians();
+    (r.cos() * length, r.sin() * length)
+  };
+
+  let snap = AngularSnap::new(10.0);
+  assert_eq!(snap.apply(polar(95.0, 0.5)), (0.0, 0.5));
+  assert_eq!(snap.apply(polar(-172.0, 0.8)), (-0.8, 0.0));
+  assert_eq!(snap.apply((0.0, 0.0)), (0.0, 0.0));
+  // outside the window the angles are spread over what's left
+  assert!(close(snap.apply(polar(27.5, 1.0)), polar(22.5, 1.0)));
+  assert!(close(snap.apply(polar(45.0, 1.0)), polar(45.0, 1.0)));
+  assert!(close(
+    snap.apply(polar(10.5, 1.0)),
+    polar(0.5 * 45.0 / 35.0, 1.0)
+  ));
+
+  let snap = AngularSnap::new(10.0).with_diagonals(5.0);
+  assert!(close(snap.apply(polar(-132.0, 1.0)), polar(-135.0, 1.0)));
+  assert!(close(snap.apply(polar(25.0, 1.0)), polar(22.5, 1.0)));
+
+  // windows that fill the whole slice leave no gaps
+  let snap = AngularSnap::new(30.0).with_diagonals(30.0);
+  assert!(close(snap.apply(polar(31.0, 1.0)), polar(45.0, 1.0)));
+
+  // windows that aren't finite don't snap at all
+  let snap = AngularSnap::new(f32::NAN).with_diagonals(f32::INFINITY);
+  assert!(close(snap.apply(polar(3.0, 1.0)), polar(3.0, 1.0)));
+  assert!(close(snap.apply(polar(47.0, 1.0)), polar(47.0, 1.0)));
+}
@@ -0,0 +1,168 @@
+//! Turning raw trigger values into something you can use.
+
+use super::{Edge, XInputState};
+
+/// A deadzone setting for one trigger.
+///
+/// Raw values at or below `inner` give 0.0, values at or above `outer` give
+/// 1.0, and values in between go smoothly from one to the other. An `outer`
+/// that isn't above `inner` makes the trigger act like a button.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct TriggerDeadzone {
+  /// The largest raw value that still counts as not pressed at all.
+  pub inner: u8,
+  /// The smallest raw value that counts as fully pressed.
+  pub outer: u8,
+}
+
+impl TriggerDeadzone {
+  /// The default deadzone, which uses `XInputState::TRIGGER_THRESHOLD` as the
+  /// inner deadzone and has no outer deadzone.
+  pub const DEFAULT: Self = TriggerDeadzone {
+    inner: XInputState::TRIGGER_THRESHOLD,
+    outer: u8::MAX,
+  };
+
+  /// A deadzone with the given inner and outer values.
+  #[inline]
+  pub fn new(inner: u8, outer: u8) -> Self {
+    TriggerDeadzone { inner, outer }
+  }
+
+  /// Applies this deadzone to a raw trigger value, giving a value from 0.0 to
+  /// 1.0.
+  pub fn apply(&self, raw_trigger: u8) -> f32 {
+    if raw_trigger <= self.inner {
+      0.0
+    } else if raw_trigger >= self.outer {
+      1.0
+    } else {
+      f32::from(raw_trigger - self.inner) / f32::from(self.outer - self.inner)
+    }
+  }
+}
+
+impl Default for TriggerDeadzone {
+  /// `DEFAULT`
+  fn default() -> Self {
+    TriggerDeadzone::DEFAULT
+  }
+}
+
+/// Uses a trigger as a button, with separate press and release points.
+///
+/// A trigger that's resting right at a single threshold will flicker between
+/// pressed and released as the value jitters. With a `TriggerButton` the
+/// trigger has to go up to `press` to become pressed, and then back down below
+/// `release` to become released again, so small jitters don't do anything.
+///
+/// This keeps track of the current pressed state, so you need one per trigger
+/// and you should `update` it with every new state.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct TriggerButton {
+  press: u8,
+  release: u8,
+  pressed: bool,
+}
+
+impl TriggerButton {
+  /// A trigger button that's pressed at `press` or above, and released below
+  /// `release`.
+  ///
+  /// If `release` is above `press` it's lowered to `press`, which gives you a
+  /// single threshold with no hysteresis.
+  #[inline]
+  pub fn new(press: u8, release: u8) -> Self {
+    TriggerButton {
+      press,
+      release: release.min(press),
+      pressed: false,
+    }
+  }
+
+  /// The press point.
+  #[inline]
+  pub fn press_threshold(&self) -> u8 {
+    self.press
+  }
+
+  /// The release point.
+  #[inline]
+  pub fn release_threshold(&self) -> u8 {
+    self.release
+  }
+
+  /// If the trigger is currently counted as pressed.
+  #[inline]
+  pub fn is_pressed(&self) -> bool {
+    self.pressed
+  }
+
+  /// Feeds in a new raw trigger value.
+  ///
+  /// The edge tells you if this caused a press or release.
+  pub fn update(&mut self, raw_trigger: u8) -> Edge {
+    let was_pressed = self.pressed;
+    self.pressed = if was_pressed {
+      raw_trigger >= self.release
+    } else {
+      raw_trigger >= self.press
+    };
+    Edge::new(was_pressed, self.pressed)
+  }
+
+  /// Forces the button back to not pressed, eg: when the controller is
+  /// disconnected.
+  #[inline]
+  pub fn reset(&mut self) {
+    self.pressed = false;
+  }
+}
+
+impl Default for TriggerButton {
+  /// Pressed at `XInputState::TRIGGER_THRESHOLD`, and released a little below
+  /// that.
+  fn default() -> Self {
+    TriggerButton::new(
+      XInputState::TRIGGER_THRESHOLD,
+      XInputState::TRIGGER_THRESHOLD - 10,
+    )
+  }
+}
+
+#[test]
+fn trigger_test() {
+  let dz = TriggerDeadzone::new(20, 220);
+  assert_eq!(dz.apply(0), 0.0);
+  assert_eq!(dz.apply(20), 0.0);
+  assert_eq!(dz.apply(120), 0.5);
+  assert_eq!(dz.apply(220), 1.0);
+  assert_eq!(dz.apply(255), 1.0);
+  let step = TriggerDeadzone::new(100, 50);
+  assert_eq!(step.apply(100), 0.0);
+  assert_eq!(step.apply(101), 1.0);
+  assert_eq!(TriggerDeadzone::DEFAULT.apply(255), 1.0);
+
+  let mut button = TriggerButton::new(100, 60);
+  let edges: Vec<Edge> = [50, 99, 100, 80, 60, 59, 99, 200]
+    .iter()
+    .map(|&raw| button.update(raw))
+    .collect();
+  assert_eq!(
+    edges,
+    vec![
+      Edge::Released,
+      Edge::Released,
+      Edge::JustPressed,
+      Edge::Held,
+      Edge::Held,
+      Edge::JustReleased,
+      Edge::Released,
+      Edge::JustPressed,
+    ]
+  );
+  button.reset();
+  assert!(!button.is_pressed());
+  assert_eq!(TriggerButton::new(10, 90).release_threshold(), 10);
+}